@@ -0,0 +1,215 @@
+//! Provides a single query answering whether a function in [`crate::functions`] can be called in the current process.
+//!
+//! [`can_call`] combines operating system support, resolution of the dedicated syscall or `apfnSimpleCall` index,
+//! the win32k system call disable mitigation policy and the known caller restrictions of the function, without
+//! invoking the function itself. [`available_calls`] and [`probe_report`] apply the same checks to all functions.
+
+use std::ffi::c_void;
+use std::fmt::Display;
+
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetProcessMitigationPolicy, ProcessSystemCallDisablePolicy,
+};
+
+use crate::{
+    error::UserCallError,
+    functions::NtUserCall,
+    resolution::{resolve, Resolution},
+};
+
+/// A process the kernel restricts a function to.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CallerRestriction {
+    /// The function may only be called by CSRSS.
+    Csrss,
+    /// The function may only be called by winlogon.
+    Winlogon,
+    /// The function may only be called by the desktop window manager.
+    Dwm,
+    /// The function may only be called by the immersive broker.
+    ImmersiveBroker,
+    /// The function requires access granted by the immersive application manager.
+    IamAccess,
+}
+
+impl NtUserCall {
+    /// Returns the process the kernel restricts this function to, if any.
+    ///
+    /// Calls from other processes fail, usually with `STATUS_ACCESS_DENIED` or `ERROR_ACCESS_DENIED`.
+    pub const fn caller_restriction(self) -> Option<CallerRestriction> {
+        match self {
+            Self::RemoteShadowSetup
+            | Self::RemoteShadowStop
+            | Self::RemotePassthruEnable
+            | Self::RemotePassthruDisable
+            | Self::WakeRITForShutdown
+            | Self::RemoteThinwireStats
+            | Self::RemoteNotify
+            | Self::RemoteShadowCleanup
+            | Self::RemoteShadowStart => Some(CallerRestriction::Csrss),
+            Self::SetWatermarkStrings | Self::DwmLockScreenUpdates => {
+                Some(CallerRestriction::Winlogon)
+            }
+            Self::EnableSessionForMMCSS | Self::RegisterGhostWindow => Some(CallerRestriction::Dwm),
+            Self::PostUIActions
+            | Self::RegisterKeyboardCorrectionCallout
+            | Self::EnableModernAppWindowKeyboardIntercept => {
+                Some(CallerRestriction::ImmersiveBroker)
+            }
+            Self::SetActiveImmersiveWindow | Self::GetWindowTrackInfoAsync => {
+                Some(CallerRestriction::IamAccess)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether a function can be called in the current process, as determined by [`can_call`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Availability {
+    /// The function can be called.
+    Available,
+    /// The function can be invoked, but the kernel restricts it to another process.
+    Restricted(CallerRestriction),
+    /// The process has been started with win32k system calls disabled via its mitigation policy.
+    Win32kDisabled,
+    /// The function cannot be invoked on the current operating system.
+    Unavailable(UserCallError),
+}
+
+impl Availability {
+    /// Returns whether the function can be invoked at all, regardless of caller restrictions.
+    pub fn is_invocable(self) -> bool {
+        matches!(self, Self::Available | Self::Restricted(_))
+    }
+}
+
+/// Returns whether `call` can be called in the current process.
+pub fn can_call(call: NtUserCall) -> Availability {
+    if win32k_syscalls_disabled() {
+        return Availability::Win32kDisabled;
+    }
+
+    if let Resolution::Error(error) = resolve(call) {
+        return Availability::Unavailable(error);
+    }
+
+    match call.caller_restriction() {
+        Some(restriction) => Availability::Restricted(restriction),
+        None => Availability::Available,
+    }
+}
+
+/// Returns whether win32k system calls are disabled for the current process via `ProcessSystemCallDisablePolicy`.
+pub fn win32k_syscalls_disabled() -> bool {
+    // Bit 0 of PROCESS_MITIGATION_SYSTEM_CALL_DISABLE_POLICY is DisallowWin32kSystemCalls.
+    let mut flags = 0u32;
+
+    // SAFETY: `flags` is valid for writes of `size_of::<u32>()` bytes, matching the size of the policy structure.
+    unsafe {
+        GetProcessMitigationPolicy(
+            GetCurrentProcess(),
+            ProcessSystemCallDisablePolicy,
+            &raw mut flags as *mut c_void,
+            size_of::<u32>(),
+        )
+    }
+    .is_ok_and(|_| flags & 1 != 0)
+}
+
+impl NtUserCall {
+    /// Returns whether this function can be invoked in the current process, regardless of caller restrictions.
+    ///
+    /// See [`can_call`] for details.
+    pub fn is_available(self) -> bool {
+        can_call(self).is_invocable()
+    }
+}
+
+/// Returns an iterator over all functions that can be invoked in the current process.
+pub fn available_calls() -> impl Iterator<Item = NtUserCall> {
+    NtUserCall::ALL
+        .into_iter()
+        .filter(|call| call.is_available())
+}
+
+/// How a function is resolved on the current operating system, as reported by [`probe_report`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ProbeOutcome {
+    /// The function is invoked via the `NtUserCall*` family of syscalls with the given `apfnSimpleCall` index.
+    Index(u16),
+    /// The function is invoked via its dedicated syscall exported from win32u.dll.
+    DedicatedExport,
+    /// The function is invoked directly via the syscall number decoded from its dedicated syscall export.
+    DirectSyscall(u16),
+    /// The function cannot be invoked.
+    Missing(UserCallError),
+}
+
+impl Display for ProbeOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "index {index}"),
+            Self::DedicatedExport => write!(f, "dedicated export"),
+            Self::DirectSyscall(number) => write!(f, "direct syscall {number:#x}"),
+            Self::Missing(error) => write!(f, "missing ({error:?})"),
+        }
+    }
+}
+
+/// The resolution of a single function, as reported by [`probe_report`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ProbeEntry {
+    pub call: NtUserCall,
+    pub outcome: ProbeOutcome,
+    pub restriction: Option<CallerRestriction>,
+}
+
+/// The resolution of all functions, as returned by [`probe_report`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProbeReport {
+    /// Whether the process has been started with win32k system calls disabled, in which case no function can be invoked.
+    pub win32k_disabled: bool,
+    /// The resolution of every function, in declaration order.
+    pub entries: Vec<ProbeEntry>,
+}
+
+impl Display for ProbeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.win32k_disabled {
+            writeln!(f, "win32k syscalls are disabled by mitigation policy")?;
+        }
+
+        for entry in &self.entries {
+            write!(f, "NtUser{}: {}", entry.call.name(), entry.outcome)?;
+
+            if let Some(restriction) = entry.restriction {
+                write!(f, " (restricted to {restriction:?})")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves all functions without invoking them and reports the outcome of each.
+pub fn probe_report() -> ProbeReport {
+    ProbeReport {
+        win32k_disabled: win32k_syscalls_disabled(),
+        entries: NtUserCall::ALL
+            .into_iter()
+            .map(|call| ProbeEntry {
+                call,
+                outcome: match resolve(call) {
+                    Resolution::Function(_) => ProbeOutcome::DedicatedExport,
+                    Resolution::Index(index, _) => ProbeOutcome::Index(index),
+                    Resolution::Syscall(number) => ProbeOutcome::DirectSyscall(number),
+                    Resolution::Error(error) => ProbeOutcome::Missing(error),
+                },
+                restriction: call.caller_restriction(),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,278 @@
+//! Provides pluggable backends the functions in [`crate::functions`] dispatch to.
+//!
+//! By default, every function invokes its syscall. Installing a [`Backend`], either process-wide via [`set_backend`]
+//! or for the duration of a closure on the current thread via [`with_backend`], redirects all functions to it. This
+//! allows code using these functions to be tested without a desktop session, e.g. with a [`MockBackend`] recording the
+//! calls and returning scripted values.
+//!
+//! Backends operate on raw values: Parameters are passed as `usize` in declaration order, and the returned `usize` is
+//! converted into the return type of the function. [`crate::context::Context`] always invokes the syscalls.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{error::UserCallError, functions::NtUserCall};
+
+/// A backend the functions in [`crate::functions`] dispatch to.
+pub trait Backend: Send + Sync {
+    /// Invokes `call` with `args`, returning the raw return value.
+    ///
+    /// # Safety
+    ///
+    /// `args` must satisfy the safety requirements of the function corresponding to `call`.
+    unsafe fn call(&self, call: NtUserCall, args: &[usize]) -> Result<usize, UserCallError>;
+}
+
+/// The backend invoking the syscalls via [`crate::raw::call`], as used when no other backend is installed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SyscallBackend;
+
+impl Backend for SyscallBackend {
+    unsafe fn call(&self, call: NtUserCall, args: &[usize]) -> Result<usize, UserCallError> {
+        // SAFETY: The caller upholds the safety requirements of the function.
+        unsafe { crate::raw::call(call, args) }
+    }
+}
+
+/// A call recorded by a [`MockBackend`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RecordedCall {
+    pub call: NtUserCall,
+    pub args: Vec<usize>,
+}
+
+/// A backend recording all calls and returning scripted values instead of invoking any syscalls.
+///
+/// For every call, the oldest value scripted via [`MockBackend::returns_once`] or [`MockBackend::fails_once`] is
+/// returned, then the value set via [`MockBackend::returns`] or [`MockBackend::fails`], and `Ok(0)` otherwise.
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    calls: Mutex<Vec<RecordedCall>>,
+    once: Mutex<HashMap<NtUserCall, VecDeque<Result<usize, UserCallError>>>>,
+    always: Mutex<HashMap<NtUserCall, Result<usize, UserCallError>>>,
+}
+
+impl MockBackend {
+    /// Creates a new backend without any scripted values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `value` for all calls to `call`.
+    pub fn returns(&self, call: NtUserCall, value: usize) -> &Self {
+        self.always
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(call, Ok(value));
+        self
+    }
+
+    /// Fails all calls to `call` with `error`.
+    pub fn fails(&self, call: NtUserCall, error: UserCallError) -> &Self {
+        self.always
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(call, Err(error));
+        self
+    }
+
+    /// Returns `value` for the next call to `call`.
+    pub fn returns_once(&self, call: NtUserCall, value: usize) -> &Self {
+        self.push_once(call, Ok(value))
+    }
+
+    /// Fails the next call to `call` with `error`.
+    pub fn fails_once(&self, call: NtUserCall, error: UserCallError) -> &Self {
+        self.push_once(call, Err(error))
+    }
+
+    fn push_once(&self, call: NtUserCall, result: Result<usize, UserCallError>) -> &Self {
+        self.once
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .entry(call)
+            .or_default()
+            .push_back(result);
+        self
+    }
+
+    /// Returns all recorded calls, in the order they were made.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Returns the number of recorded calls to `call`.
+    pub fn call_count(&self, call: NtUserCall) -> usize {
+        self.calls
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .filter(|recorded| recorded.call == call)
+            .count()
+    }
+
+    /// Returns whether `call` has been called.
+    pub fn was_called(&self, call: NtUserCall) -> bool {
+        self.call_count(call) != 0
+    }
+
+    /// Clears the recorded calls, keeping the scripted values.
+    pub fn clear_calls(&self) {
+        self.calls
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+}
+
+impl Backend for MockBackend {
+    unsafe fn call(&self, call: NtUserCall, args: &[usize]) -> Result<usize, UserCallError> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(RecordedCall {
+                call,
+                args: args.to_vec(),
+            });
+
+        if let Some(result) = self
+            .once
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get_mut(&call)
+            .and_then(VecDeque::pop_front)
+        {
+            return result;
+        }
+
+        self.always
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&call)
+            .copied()
+            .unwrap_or(Ok(0))
+    }
+}
+
+/// Whether a backend is installed for all threads, so that calls only look it up if one is installed.
+static GLOBAL_INSTALLED: AtomicBool = AtomicBool::new(false);
+static GLOBAL_BACKEND: RwLock<Option<Arc<dyn Backend>>> = RwLock::new(None);
+
+thread_local! {
+    static SCOPED_BACKENDS: RefCell<Vec<Arc<dyn Backend>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Installs `backend` for all threads, replacing the previously installed one. `None` restores the syscalls.
+///
+/// Backends installed via [`with_backend`] take precedence.
+pub fn set_backend(backend: Option<Arc<dyn Backend>>) {
+    let mut global = GLOBAL_BACKEND
+        .write()
+        .unwrap_or_else(|err| err.into_inner());
+
+    GLOBAL_INSTALLED.store(backend.is_some(), Ordering::Relaxed);
+    *global = backend;
+}
+
+/// Runs `f` with `backend` installed on the current thread.
+pub fn with_backend<T>(backend: Arc<dyn Backend>, f: impl FnOnce() -> T) -> T {
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            SCOPED_BACKENDS.with_borrow_mut(Vec::pop);
+        }
+    }
+
+    SCOPED_BACKENDS.with_borrow_mut(|backends| backends.push(backend));
+
+    let _guard = Guard;
+    f()
+}
+
+/// Returns whether a backend is installed for the calling thread or all threads.
+pub(crate) fn is_installed() -> bool {
+    SCOPED_BACKENDS.with_borrow(|backends| !backends.is_empty())
+        || GLOBAL_INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Dispatches `call` to the installed backend, if any.
+///
+/// # Safety
+///
+/// The arguments returned by `args` must satisfy the safety requirements of the function corresponding to `call`.
+#[inline]
+pub(crate) unsafe fn dispatch_override<A: AsRef<[usize]>>(
+    call: NtUserCall,
+    args: impl FnOnce() -> A,
+) -> Option<Result<usize, UserCallError>> {
+    let backend = SCOPED_BACKENDS
+        .with_borrow(|backends| backends.last().cloned())
+        .or_else(|| {
+            GLOBAL_INSTALLED
+                .load(Ordering::Relaxed)
+                .then(|| {
+                    GLOBAL_BACKEND
+                        .read()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .clone()
+                })
+                .flatten()
+        })?;
+
+    // SAFETY: The caller guarantees that the arguments satisfy the safety requirements of the function.
+    Some(unsafe { backend.call(call, args().as_ref()) })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{
+        backend::{with_backend, MockBackend, RecordedCall},
+        error::UserCallError,
+        functions::{NtUserCall, NtUserCreateMenu, NtUserSetCaretPos},
+    };
+
+    #[test]
+    pub fn mock_backend_should_record_calls_and_return_scripted_values() {
+        let error = UserCallError::CallNotFound {
+            export: None,
+            last_error: None,
+        };
+        let backend = Arc::new(MockBackend::new());
+        backend
+            .returns(NtUserCall::SetCaretPos, 1)
+            .fails_once(NtUserCall::CreateMenu, error);
+
+        with_backend(backend.clone(), || {
+            // SAFETY: The calls are dispatched to the mock backend.
+            unsafe {
+                assert_eq!(NtUserCreateMenu(), Err(error));
+                assert_eq!(
+                    NtUserSetCaretPos(4, 2).map(|result| result.as_bool()),
+                    Ok(true)
+                );
+            }
+        });
+
+        assert_eq!(
+            backend.calls(),
+            [
+                RecordedCall {
+                    call: NtUserCall::CreateMenu,
+                    args: vec![],
+                },
+                RecordedCall {
+                    call: NtUserCall::SetCaretPos,
+                    args: vec![4, 2],
+                },
+            ]
+        );
+    }
+}
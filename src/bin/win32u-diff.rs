@@ -0,0 +1,53 @@
+//! Prints the changes of the `NtUser*` exports between two `win32u.dll` files and the crate entries they affect.
+//!
+//! Usage: `win32u-diff <old win32u.dll> <new win32u.dll>`
+
+use std::process::ExitCode;
+
+use nt_user_call::devtools::{diff, Win32uExports};
+
+fn main() -> ExitCode {
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+
+    let [old, new] = args.as_slice() else {
+        eprintln!("Usage: win32u-diff <old win32u.dll> <new win32u.dll>");
+        return ExitCode::FAILURE;
+    };
+
+    let read = |path| {
+        Win32uExports::read(path).map_err(|err| {
+            eprintln!(
+                "Could not read {}: {err}",
+                std::path::Path::new(path).display()
+            );
+        })
+    };
+
+    let (Ok(old), Ok(new)) = (read(old), read(new)) else {
+        return ExitCode::FAILURE;
+    };
+
+    if old.machine != new.machine {
+        eprintln!(
+            "Warning: comparing files for different machines ({:#06x} and {:#06x})",
+            old.machine, new.machine
+        );
+    }
+
+    let changes = diff(&old, &new);
+
+    for change in &changes {
+        println!("{change}");
+    }
+
+    println!(
+        "{} changes, {} affecting crate entries",
+        changes.len(),
+        changes
+            .iter()
+            .filter(|change| change.affected().is_some())
+            .count()
+    );
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,235 @@
+//! Provides a report of the runtime capabilities of this crate, for attaching to bug reports.
+//!
+//! [`capabilities`] collects the crate version, the enabled cargo features, the detected operating system, the
+//! mechanism functions are invoked with and any anomalies encountered during detection. With the `serde` feature,
+//! [`Capabilities`] implements `Serialize`.
+
+use std::fmt::Display;
+
+use windows::{
+    core::{s, w},
+    Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+};
+
+use crate::{
+    availability::win32k_syscalls_disabled,
+    error::UserCallError,
+    version::{
+        environment, get_os_version, has_dedicated_syscalls, os_build, Environment, OsBuild,
+        OsVersion, PINNED_OS_VERSION,
+    },
+};
+
+/// The cargo features this crate has been compiled with.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "all_os_versions")]
+    "all_os_versions",
+    #[cfg(feature = "backend")]
+    "backend",
+    #[cfg(feature = "devtools")]
+    "devtools",
+    #[cfg(feature = "direct-syscalls")]
+    "direct-syscalls",
+    #[cfg(feature = "hresult-api")]
+    "hresult-api",
+    #[cfg(feature = "instrumentation")]
+    "instrumentation",
+    #[cfg(feature = "pin-win7")]
+    "pin-win7",
+    #[cfg(feature = "pin-win8")]
+    "pin-win8",
+    #[cfg(feature = "pin-win81")]
+    "pin-win81",
+    #[cfg(feature = "pin-win10")]
+    "pin-win10",
+    #[cfg(feature = "serde")]
+    "serde",
+    #[cfg(feature = "std")]
+    "std",
+    #[cfg(feature = "tracing")]
+    "tracing",
+    #[cfg(feature = "unsafe-direct")]
+    "unsafe-direct",
+    #[cfg(feature = "win11-only")]
+    "win11-only",
+];
+
+/// How the functions in [`crate::functions`] are invoked.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Mechanism {
+    /// Via their dedicated syscalls exported from win32u.dll.
+    DedicatedSyscalls,
+    /// Directly via the syscall numbers decoded from their dedicated syscall stubs in win32u.dll, bypassing the stubs.
+    DirectSyscalls,
+    /// Via their `apfnSimpleCall` indices and the `NtUserCall*` family of syscalls.
+    UserCallFamily,
+    /// Via the `apfnSimpleCall` indices of the operating system version pinned at compile time and the `NtUserCall*`
+    /// family of syscalls.
+    PinnedIndices,
+    /// Via a backend installed via `crate::backend` for the calling thread or all threads.
+    Backend,
+}
+
+impl Display for Mechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DedicatedSyscalls => write!(f, "dedicated win32u.dll syscalls"),
+            Self::DirectSyscalls => write!(f, "direct syscalls decoded from win32u.dll"),
+            Self::UserCallFamily => write!(f, "NtUserCall* family of syscalls"),
+            Self::PinnedIndices => write!(f, "NtUserCall* family of syscalls with pinned indices"),
+            Self::Backend => write!(f, "installed backend"),
+        }
+    }
+}
+
+/// Returns how the functions in [`crate::functions`] are invoked on the calling thread.
+pub(crate) fn mechanism() -> Mechanism {
+    #[cfg(feature = "backend")]
+    if crate::backend::is_installed() {
+        return Mechanism::Backend;
+    }
+
+    if PINNED_OS_VERSION.is_some() {
+        Mechanism::PinnedIndices
+    } else if cfg!(feature = "win11-only") {
+        Mechanism::DedicatedSyscalls
+    } else if has_dedicated_syscalls() {
+        if cfg!(feature = "direct-syscalls") {
+            Mechanism::DirectSyscalls
+        } else {
+            Mechanism::DedicatedSyscalls
+        }
+    } else {
+        Mechanism::UserCallFamily
+    }
+}
+
+/// An inconsistency encountered while detecting the capabilities.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Anomaly {
+    /// The operating system version could not be mapped to a supported version.
+    UnsupportedOsVersion,
+    /// win32u.dll is not loaded even though the operating system ships it.
+    Win32uNotLoaded,
+    /// The presence of dedicated syscall exports in win32u.dll does not match the detected operating system version.
+    DedicatedSyscallsMismatch {
+        /// Whether the detected operating system version uses dedicated syscalls.
+        expected: bool,
+    },
+    /// The process has been started with win32k system calls disabled via its mitigation policy.
+    Win32kSyscallsDisabled,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedOsVersion => write!(f, "the operating system version is not supported"),
+            Self::Win32uNotLoaded => write!(f, "win32u.dll is not loaded"),
+            Self::DedicatedSyscallsMismatch { expected: true } => write!(
+                f,
+                "win32u.dll lacks the dedicated syscall exports expected on this operating system version"
+            ),
+            Self::DedicatedSyscallsMismatch { expected: false } => write!(
+                f,
+                "win32u.dll has dedicated syscall exports not expected on this operating system version"
+            ),
+            Self::Win32kSyscallsDisabled => {
+                write!(f, "win32k syscalls are disabled by mitigation policy")
+            }
+        }
+    }
+}
+
+/// The runtime capabilities of this crate, as returned by [`capabilities`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Capabilities {
+    /// The version of this crate.
+    pub crate_version: &'static str,
+    /// The cargo features this crate has been compiled with.
+    pub features: &'static [&'static str],
+    /// The target architecture this crate has been compiled for.
+    pub target_arch: &'static str,
+    /// The version and build number of the operating system.
+    pub os_build: OsBuild,
+    /// The implementation of the Windows API the process is running on.
+    pub environment: Environment,
+    /// The detected operating system version, or the error its detection failed with.
+    pub os_version: Result<OsVersion, UserCallError>,
+    /// How the functions in [`crate::functions`] are invoked on the thread that collected the capabilities.
+    pub mechanism: Mechanism,
+    /// Inconsistencies encountered while detecting the capabilities.
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "nt-user-call {} ({})",
+            self.crate_version, self.target_arch
+        )?;
+        writeln!(f, "features: {}", self.features.join(", "))?;
+        writeln!(f, "OS build: {}", self.os_build)?;
+        writeln!(f, "environment: {:?}", self.environment)?;
+
+        match self.os_version {
+            Ok(os_version) => writeln!(f, "OS version: {os_version:?}")?,
+            Err(error) => writeln!(f, "OS version: {error:?}")?,
+        }
+
+        write!(f, "mechanism: {}", self.mechanism)?;
+
+        for anomaly in &self.anomalies {
+            write!(f, "\nanomaly: {anomaly}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Detects the runtime capabilities of this crate in the current process.
+pub fn capabilities() -> Capabilities {
+    let os_version = get_os_version();
+    let dedicated_syscalls = cfg!(feature = "win11-only") || has_dedicated_syscalls();
+    let mut anomalies = Vec::new();
+
+    if let Err(UserCallError::OsNotSupported { .. }) = os_version {
+        anomalies.push(Anomaly::UnsupportedOsVersion);
+    }
+
+    // SAFETY: `GetModuleHandleW` is called with a valid LPCWSTR.
+    match unsafe { GetModuleHandleW(w!("win32u")) } {
+        Ok(win32u) => {
+            // SAFETY: `GetProcAddress` is called with a valid HMODULE and a valid LPCSTR.
+            let exported = unsafe { GetProcAddress(win32u, s!("NtUserGetInputEvent")) }.is_some();
+
+            if exported != dedicated_syscalls {
+                anomalies.push(Anomaly::DedicatedSyscallsMismatch {
+                    expected: dedicated_syscalls,
+                });
+            }
+        }
+        Err(_) if dedicated_syscalls || matches!(os_version, Ok(OsVersion::Win10(_))) => {
+            anomalies.push(Anomaly::Win32uNotLoaded);
+        }
+        Err(_) => {}
+    }
+
+    if win32k_syscalls_disabled() {
+        anomalies.push(Anomaly::Win32kSyscallsDisabled);
+    }
+
+    Capabilities {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        features: FEATURES,
+        target_arch: std::env::consts::ARCH,
+        os_build: os_build(),
+        environment: environment(),
+        os_version,
+        mechanism: mechanism(),
+        anomalies,
+    }
+}
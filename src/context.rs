@@ -0,0 +1,216 @@
+//! Provides [`Context`], the entry point owning all resolution state.
+//!
+//! A [`Context`] owns the detected environment and operating system version, the resolution of every function, the
+//! index tables registered via [`Context::register_table`] and the indices discovered via [`Context::discover`]. This
+//! allows for dependency injection, deterministic teardown and multiple differently configured contexts in the same
+//! process. All functions in [`crate::functions`] are available as methods of the same name.
+//!
+//! The free functions in [`crate::functions`], [`crate::raw`], [`crate::indices`] and [`crate::version`] delegate to
+//! the process-wide context returned by [`Context::global`].
+
+use std::fmt::Debug;
+use std::sync::{OnceLock, RwLock};
+
+use crate::{
+    error::UserCallError,
+    functions::{Family, NtUserCall},
+    indices::{get_index_for, registered_index, DiscoveredIndices, IndexTable},
+    resolution::{resolve_with, Resolution, ResolutionCell},
+    version::{detect_environment, os_version_for, Environment, OsVersion, PINNED_OS_VERSION},
+};
+
+/// The configuration of a [`Context`].
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ContextConfig {
+    os_version: Option<OsVersion>,
+    dedicated_syscalls: Option<bool>,
+    direct_syscalls: Option<bool>,
+}
+
+impl ContextConfig {
+    /// Creates a configuration detecting all settings from the current operating system.
+    pub const fn new() -> Self {
+        Self {
+            os_version: None,
+            dedicated_syscalls: None,
+            direct_syscalls: None,
+        }
+    }
+
+    /// Uses the `apfnSimpleCall` indices and syscall numbers of `os_version` instead of the detected version.
+    pub fn os_version(mut self, os_version: OsVersion) -> Self {
+        self.os_version = Some(os_version);
+        self
+    }
+
+    /// Sets whether functions are invoked via their dedicated syscalls exported from win32u.dll instead of the
+    /// `NtUserCall*` family of syscalls. By default, dedicated syscalls are used starting with Windows 11.
+    pub fn dedicated_syscalls(mut self, dedicated_syscalls: bool) -> Self {
+        self.dedicated_syscalls = Some(dedicated_syscalls);
+        self
+    }
+
+    /// Sets whether syscalls exported from win32u.dll are invoked directly via the syscall number decoded from their
+    /// stub instead of calling the stub, which bypasses user-mode hooks. Only supported on x64 and under WOW64; functions
+    /// fail with [`UserCallError::ArchitectureNotSupported`] otherwise. By default, this is enabled with the
+    /// `direct-syscalls` feature.
+    pub fn direct_syscalls(mut self, direct_syscalls: bool) -> Self {
+        self.direct_syscalls = Some(direct_syscalls);
+        self
+    }
+}
+
+/// Owns the resolution state of all functions in [`crate::functions`], which are available as methods.
+///
+/// Functions are resolved the first time they are called on a context, and the outcome is cached in the context.
+pub struct Context {
+    config: ContextConfig,
+    environment: OnceLock<Environment>,
+    pub(crate) os_version: OnceLock<Result<OsVersion, UserCallError>>,
+    calls: [ResolutionCell; NtUserCall::COUNT],
+    families: [ResolutionCell; Family::COUNT],
+    tables: RwLock<Vec<IndexTable>>,
+    discovered: OnceLock<DiscoveredIndices>,
+}
+
+/// The context of the free functions, which uses the operating system version pinned via the `pin-*` features.
+static GLOBAL: Context = Context::with_config(ContextConfig {
+    os_version: PINNED_OS_VERSION,
+    ..ContextConfig::new()
+});
+
+impl Context {
+    const fn with_config(config: ContextConfig) -> Self {
+        Self {
+            config,
+            environment: OnceLock::new(),
+            os_version: OnceLock::new(),
+            calls: [const { ResolutionCell::new() }; NtUserCall::COUNT],
+            families: [const { ResolutionCell::new() }; Family::COUNT],
+            tables: RwLock::new(Vec::new()),
+            discovered: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new context with `config`.
+    ///
+    /// Fails with the error of the operating system version detection unless dedicated syscalls are used.
+    pub fn new(config: ContextConfig) -> Result<Self, UserCallError> {
+        let context = Self::with_config(config);
+
+        if !context.has_dedicated_syscalls() {
+            context.os_version()?;
+        }
+
+        Ok(context)
+    }
+
+    /// Returns the process-wide context the free functions of this crate delegate to.
+    ///
+    /// Unlike contexts created via [`Context::new`], it detects the operating system version on first use, and
+    /// detection errors are returned by the functions instead.
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    /// Returns the implementation of the Windows API the process is running on, detected on the first call.
+    pub fn environment(&self) -> Environment {
+        *self.environment.get_or_init(detect_environment)
+    }
+
+    /// Returns the operating system version the context has been created for.
+    pub fn os_version(&self) -> Result<OsVersion, UserCallError> {
+        *self.os_version.get_or_init(|| {
+            self.config
+                .os_version
+                .map_or_else(|| os_version_for(self.environment()), Ok)
+        })
+    }
+
+    /// Returns whether functions are invoked via their dedicated syscalls exported from win32u.dll.
+    pub fn has_dedicated_syscalls(&self) -> bool {
+        self.config
+            .dedicated_syscalls
+            .unwrap_or_else(|| matches!(self.os_version(), Err(UserCallError::OsTooNew { .. })))
+    }
+
+    /// Returns whether syscalls are invoked directly via their syscall number.
+    pub fn has_direct_syscalls(&self) -> bool {
+        self.config
+            .direct_syscalls
+            .unwrap_or(cfg!(feature = "direct-syscalls"))
+    }
+
+    /// Registers `table` with this context. See [`crate::indices::register_table`].
+    pub fn register_table(&self, table: IndexTable) {
+        self.tables
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(table);
+    }
+
+    /// Removes all tables registered with this context.
+    pub fn clear_tables(&self) {
+        self.tables
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+
+    /// Returns the `apfnSimpleCall` index of `call` from the tables registered with this context or the built-in
+    /// tables of its operating system version. See [`crate::indices::get_index`].
+    pub fn get_index(&self, call: NtUserCall) -> Option<u16> {
+        registered_index(
+            &self.tables.read().unwrap_or_else(|err| err.into_inner()),
+            call,
+        )
+        .or_else(|| get_index_for(self.os_version(), call))
+    }
+
+    /// Discovers the `apfnSimpleCall` indices of the running build from user32.dll, resolving the `NtUserCall*`
+    /// syscalls for the operating system version of this context. See [`crate::indices::discover`].
+    pub fn discover(&self) -> Result<&DiscoveredIndices, UserCallError> {
+        if let Some(discovered) = self.discovered.get() {
+            return Ok(discovered);
+        }
+
+        let discovered = crate::indices::discover_indices(self)?;
+
+        Ok(self.discovered.get_or_init(|| discovered))
+    }
+
+    /// Returns the cached resolution of `call`, resolving it on the first call.
+    #[inline]
+    pub(crate) fn resolve(&self, call: NtUserCall) -> Resolution {
+        self.calls[call as usize].get_or_resolve(|| self.resolve_uncached(call))
+    }
+
+    /// Resolves how `call` is invoked on this context without consulting or updating the cache.
+    pub(crate) fn resolve_uncached(&self, call: NtUserCall) -> Resolution {
+        resolve_with(
+            call,
+            self.has_dedicated_syscalls(),
+            self.has_direct_syscalls(),
+            self.os_version(),
+            || self.get_index(call),
+            |family| self.resolve_family(family),
+        )
+    }
+
+    /// Returns the cached resolution of `family`, resolving it on the first call.
+    #[inline]
+    pub(crate) fn resolve_family(&self, family: Family) -> Resolution {
+        self.families[family as usize]
+            .get_or_resolve(|| family.resolve(self.os_version(), self.has_direct_syscalls()))
+    }
+}
+
+impl Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("config", &self.config)
+            .field("environment", &self.environment.get())
+            .field("os_version", &self.os_version.get())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,150 @@
+//! Provides helpers for observing the input desktop, built on [`NtUserGetInputDesktop`].
+//!
+//! The input desktop changes whenever Windows switches to a different desktop to receive user input, most notably
+//! to the `Winlogon` secure desktop for the logon screen, the lock screen, the `Ctrl+Alt+Del` screen and
+//! UAC prompts. Screen capture and automation tools cannot interact with that desktop and should pause until the
+//! input desktop switches back.
+//!
+//! Unless the process runs as `SYSTEM`, the secure desktop cannot be opened, so [`InputDesktopWatcher`] reports it as
+//! an inaccessible desktop without a name.
+
+use std::ffi::c_void;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use windows::Win32::{
+    Foundation::HANDLE,
+    System::StationsAndDesktops::{GetUserObjectInformationW, HDESK, UOI_NAME},
+};
+
+use crate::functions::NtUserGetInputDesktop;
+
+/// The name of the secure desktop used for logon, lock screen and UAC prompts.
+pub const SECURE_DESKTOP_NAME: &str = "Winlogon";
+
+/// Returns the name of the desktop currently receiving user input, e.g. `Default` or `Winlogon`.
+pub fn input_desktop_name() -> windows::core::Result<String> {
+    // SAFETY: `NtUserGetInputDesktop` takes no parameters and the returned handle is not owned by the caller.
+    let desktop = unsafe { NtUserGetInputDesktop() }?;
+
+    desktop_name(desktop)
+}
+
+fn desktop_name(desktop: HDESK) -> windows::core::Result<String> {
+    let mut length = 0u32;
+
+    // SAFETY: Querying the required length with a null buffer is explicitly supported. The call is expected to fail
+    // with ERROR_INSUFFICIENT_BUFFER, the actual error is reported by the second call.
+    _ = unsafe {
+        GetUserObjectInformationW(HANDLE(desktop.0), UOI_NAME, None, 0, Some(&raw mut length))
+    };
+
+    let mut buffer = vec![0u16; (length as usize).div_ceil(size_of::<u16>()).max(1)];
+
+    // SAFETY: `buffer` is valid for writes of `buffer.len() * size_of::<u16>()` bytes.
+    unsafe {
+        GetUserObjectInformationW(
+            HANDLE(desktop.0),
+            UOI_NAME,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            (buffer.len() * size_of::<u16>()) as _,
+            Some(&raw mut length),
+        )
+    }?;
+
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Ok(String::from_utf16_lossy(&buffer[..end]))
+}
+
+/// Returns the name of the input desktop, or `None` if it cannot be opened or queried by the current process.
+fn accessible_input_desktop_name() -> windows::core::Result<Option<String>> {
+    // SAFETY: `NtUserGetInputDesktop` takes no parameters and the returned handle is not owned by the caller.
+    let desktop = unsafe { NtUserGetInputDesktop() }?;
+
+    if desktop.is_invalid() {
+        return Ok(None);
+    }
+
+    Ok(desktop_name(desktop).ok())
+}
+
+/// A switch of the input desktop, as reported by [`InputDesktopWatcher`].
+///
+/// Desktops that cannot be opened by the current process, most notably the secure desktop, are reported as `None`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct InputDesktopSwitch {
+    pub previous: Option<String>,
+    pub current: Option<String>,
+}
+
+impl InputDesktopSwitch {
+    /// Returns whether input switched to the secure desktop or another inaccessible desktop.
+    pub fn entered_secure_desktop(&self) -> bool {
+        self.current.as_deref().is_none_or(is_secure_desktop)
+    }
+
+    /// Returns whether input switched away from the secure desktop or another inaccessible desktop.
+    pub fn left_secure_desktop(&self) -> bool {
+        self.previous.as_deref().is_none_or(is_secure_desktop)
+    }
+}
+
+/// Returns whether `name` is the name of the secure desktop.
+pub fn is_secure_desktop(name: &str) -> bool {
+    name.eq_ignore_ascii_case(SECURE_DESKTOP_NAME)
+}
+
+/// Polls the input desktop and reports switches between desktops.
+#[derive(Clone, Debug)]
+pub struct InputDesktopWatcher {
+    current: Option<String>,
+}
+
+impl InputDesktopWatcher {
+    /// Creates a new watcher, sampling the current input desktop.
+    pub fn new() -> windows::core::Result<Self> {
+        Ok(Self {
+            current: accessible_input_desktop_name()?,
+        })
+    }
+
+    /// Returns the name of the input desktop as of the last poll, or `None` if it could not be opened.
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Samples the input desktop and returns the switch since the last poll, if any.
+    ///
+    /// Failing to open the input desktop is reported as a switch to an inaccessible desktop. Errors are only returned
+    /// if [`NtUserGetInputDesktop`] cannot be called at all.
+    pub fn poll(&mut self) -> windows::core::Result<Option<InputDesktopSwitch>> {
+        let name = accessible_input_desktop_name()?;
+
+        if name == self.current {
+            return Ok(None);
+        }
+
+        Ok(Some(InputDesktopSwitch {
+            previous: std::mem::replace(&mut self.current, name.clone()),
+            current: name,
+        }))
+    }
+
+    /// Polls the input desktop every `interval` and invokes `callback` for every switch until it returns
+    /// [`ControlFlow::Break`] or [`InputDesktopWatcher::poll`] fails.
+    pub fn watch<B>(
+        &mut self,
+        interval: Duration,
+        mut callback: impl FnMut(&InputDesktopSwitch) -> ControlFlow<B>,
+    ) -> windows::core::Result<B> {
+        loop {
+            if let Some(switch) = self.poll()? {
+                if let ControlFlow::Break(value) = callback(&switch) {
+                    return Ok(value);
+                }
+            }
+
+            std::thread::sleep(interval);
+        }
+    }
+}
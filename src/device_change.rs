@@ -0,0 +1,160 @@
+//! Provides a watcher for device changes, built on [`NtUserGetDeviceChangeInfo`].
+//!
+//! Every call to [`NtUserGetDeviceChangeInfo`] returns the device changes pending since the previous call as a bit
+//! mask and resets them. [`DeviceChangeWatcher`] drains the pending changes and decodes every set bit into one
+//! [`DeviceChangeEvent`], offering input device tooling an alternative to handling `WM_DEVICECHANGE` in a window
+//! procedure.
+//!
+//! The meaning of the individual bits is undocumented, so changes are identified by their bit without further
+//! interpretation.
+
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::{error::UserCallError, functions::NtUserGetDeviceChangeInfo};
+
+/// A single pending device change, identified by its bit in the mask returned by [`NtUserGetDeviceChangeInfo`].
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DeviceChange(u32);
+
+impl DeviceChange {
+    /// Returns the bit of this change in the mask returned by [`NtUserGetDeviceChangeInfo`].
+    pub fn bit(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the index of the bit of this change.
+    pub fn index(self) -> u32 {
+        self.0.trailing_zeros()
+    }
+}
+
+/// The device changes returned by a single call to [`NtUserGetDeviceChangeInfo`].
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DeviceChangeInfo(u32);
+
+impl DeviceChangeInfo {
+    /// Creates the device changes from the mask returned by [`NtUserGetDeviceChangeInfo`].
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the mask returned by [`NtUserGetDeviceChangeInfo`].
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether no device changes are pending.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether `change` is pending.
+    pub fn contains(self, change: DeviceChange) -> bool {
+        self.0 & change.0 != 0
+    }
+
+    /// Returns an iterator over the individual pending device changes, in ascending bit order.
+    pub fn iter(self) -> impl Iterator<Item = DeviceChange> {
+        (0..u32::BITS)
+            .map(|index| 1 << index)
+            .filter(move |bit| self.0 & bit != 0)
+            .map(DeviceChange)
+    }
+
+    /// Returns an iterator over the events decoded from the pending device changes, one per set bit, in ascending bit
+    /// order.
+    pub fn events(self) -> impl Iterator<Item = DeviceChangeEvent> {
+        self.iter().map(DeviceChangeEvent::Changed)
+    }
+}
+
+/// A device change decoded from a single set bit.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum DeviceChangeEvent {
+    /// The device identified by the bit has changed. As the bits are undocumented, all changes are reported as this
+    /// kind.
+    Changed(DeviceChange),
+}
+
+impl DeviceChangeEvent {
+    /// Returns the device change the event has been decoded from.
+    pub fn change(self) -> DeviceChange {
+        match self {
+            Self::Changed(change) => change,
+        }
+    }
+}
+
+/// Returns and resets the pending device changes.
+pub fn device_change_info() -> Result<DeviceChangeInfo, UserCallError> {
+    // SAFETY: `NtUserGetDeviceChangeInfo` takes no parameters.
+    unsafe { NtUserGetDeviceChangeInfo() }.map(DeviceChangeInfo::from_bits)
+}
+
+/// Drains pending device changes and delivers them as [`DeviceChangeEvent`]s.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceChangeWatcher;
+
+impl DeviceChangeWatcher {
+    /// Creates a new watcher. Device changes pending before the first poll are reported by the first poll.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Drains the pending device changes and returns their events, in ascending bit order.
+    pub fn poll(&mut self) -> Result<Vec<DeviceChangeEvent>, UserCallError> {
+        Ok(device_change_info()?.events().collect())
+    }
+
+    /// Drains pending device changes every `interval` and invokes `callback` for every event until it returns
+    /// [`ControlFlow::Break`] or polling fails.
+    pub fn watch<B>(
+        &mut self,
+        interval: Duration,
+        mut callback: impl FnMut(DeviceChangeEvent) -> ControlFlow<B>,
+    ) -> Result<B, UserCallError> {
+        loop {
+            for event in self.poll()? {
+                if let ControlFlow::Break(value) = callback(event) {
+                    return Ok(value);
+                }
+            }
+
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::device_change::{DeviceChangeEvent, DeviceChangeInfo};
+
+    #[test]
+    pub fn device_change_info_should_iterate_set_bits() {
+        let bits: Vec<_> = DeviceChangeInfo::from_bits(0x8000_0005)
+            .iter()
+            .map(|change| change.bit())
+            .collect();
+
+        assert_eq!(bits, [0x1, 0x4, 0x8000_0000]);
+    }
+
+    #[test]
+    pub fn consecutive_polls_should_report_each_pending_change_once() {
+        let polls: Vec<Vec<_>> = [0b0011, 0b0000, 0b0010]
+            .into_iter()
+            .map(|bits| {
+                DeviceChangeInfo::from_bits(bits)
+                    .events()
+                    .map(|event| match event {
+                        DeviceChangeEvent::Changed(change) => change.bit(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(polls, [vec![0b0001, 0b0010], vec![], vec![0b0010]]);
+    }
+}
@@ -0,0 +1,236 @@
+//! Provides developer tooling for keeping the crate current with new Windows builds.
+//!
+//! [`diff`] compares the `NtUser*` exports of two `win32u.dll` files, including the syscall numbers embedded in their
+//! stubs, and maps every change to the affected [`NtUserCall`] entry or `NtUserCall*` syscall. The `win32u-diff`
+//! binary prints that report for two files given on the command line.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::{
+    functions::{Family, NtUserCall},
+    pe::{syscall_number, Image, Layout},
+};
+
+/// The `NtUser*` exports of a `win32u.dll` file, mapped to the syscall numbers of their stubs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Win32uExports {
+    /// The `IMAGE_FILE_MACHINE_*` value of the file.
+    pub machine: u16,
+    /// The export names, mapped to their syscall number if the stub could be decoded.
+    pub exports: BTreeMap<String, Option<u32>>,
+}
+
+impl Win32uExports {
+    /// Reads the exports of the `win32u.dll` file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses the exports of a `win32u.dll` file read into `data`.
+    pub fn parse(data: &[u8]) -> std::io::Result<Self> {
+        let image = Image::parse(data, Layout::File)?;
+        let machine = image.machine();
+
+        let exports = image
+            .exports()?
+            .into_iter()
+            .filter(|export| export.name.starts_with("NtUser") && !export.forwarded)
+            .map(|export| {
+                Ok((
+                    export.name.to_owned(),
+                    syscall_number(machine, image.bytes(export.rva, 8)?),
+                ))
+            })
+            .collect::<std::io::Result<_>>()?;
+
+        Ok(Self { machine, exports })
+    }
+}
+
+/// A change of a single export between two `win32u.dll` files.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ExportChange {
+    Added {
+        name: String,
+        syscall_number: Option<u32>,
+    },
+    Removed {
+        name: String,
+        syscall_number: Option<u32>,
+    },
+    Renumbered {
+        name: String,
+        old: Option<u32>,
+        new: Option<u32>,
+    },
+}
+
+impl ExportChange {
+    /// Returns the name of the changed export.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Added { name, .. }
+            | Self::Removed { name, .. }
+            | Self::Renumbered { name, .. } => name,
+        }
+    }
+
+    /// Returns the crate entry affected by this change, if any.
+    pub fn affected(&self) -> Option<Affected> {
+        let name = self.name();
+
+        if let Some(call) = name.strip_prefix("NtUser").and_then(NtUserCall::from_name) {
+            Some(Affected::Call(call))
+        } else {
+            Family::from_name(name).map(|_| Affected::Syscall(name.to_owned()))
+        }
+    }
+}
+
+impl Display for ExportChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Number(Option<u32>);
+
+        impl Display for Number {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self.0 {
+                    Some(number) => write!(f, "{number:#x}"),
+                    None => write!(f, "?"),
+                }
+            }
+        }
+
+        match self {
+            Self::Added {
+                name,
+                syscall_number,
+            } => write!(f, "+ {name} ({})", Number(*syscall_number))?,
+            Self::Removed {
+                name,
+                syscall_number,
+            } => write!(f, "- {name} ({})", Number(*syscall_number))?,
+            Self::Renumbered { name, old, new } => {
+                write!(f, "~ {name} ({} -> {})", Number(*old), Number(*new))?
+            }
+        }
+
+        match self.affected() {
+            Some(affected) => write!(f, " [affects {affected}]"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A crate entry affected by an [`ExportChange`].
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Affected {
+    /// A function in [`crate::functions`].
+    Call(NtUserCall),
+    /// One of the `NtUserCall*` syscalls.
+    Syscall(String),
+}
+
+impl Display for Affected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Call(call) => write!(f, "NtUserCall::{}", call.name()),
+            Self::Syscall(name) => write!(f, "user_call::{name}"),
+        }
+    }
+}
+
+/// Returns all changes of the exports between `old` and `new`, ordered by export name.
+pub fn diff(old: &Win32uExports, new: &Win32uExports) -> Vec<ExportChange> {
+    let mut changes: Vec<_> = old
+        .exports
+        .iter()
+        .filter_map(|(name, &old_number)| match new.exports.get(name) {
+            None => Some(ExportChange::Removed {
+                name: name.clone(),
+                syscall_number: old_number,
+            }),
+            Some(&new_number) if new_number != old_number => Some(ExportChange::Renumbered {
+                name: name.clone(),
+                old: old_number,
+                new: new_number,
+            }),
+            Some(_) => None,
+        })
+        .chain(
+            new.exports
+                .iter()
+                .filter(|(name, _)| !old.exports.contains_key(*name))
+                .map(|(name, &new_number)| ExportChange::Added {
+                    name: name.clone(),
+                    syscall_number: new_number,
+                }),
+        )
+        .collect();
+
+    changes.sort_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        devtools::{diff, Affected, ExportChange, Win32uExports},
+        functions::NtUserCall,
+    };
+
+    #[test]
+    pub fn diff_should_report_changed_exports() {
+        let old = Win32uExports {
+            machine: 0,
+            exports: [
+                ("NtUserCreateMenu".to_owned(), Some(1)),
+                ("NtUserCallNoParam".to_owned(), Some(2)),
+                ("NtUserUnchanged".to_owned(), Some(3)),
+            ]
+            .into(),
+        };
+
+        let new = Win32uExports {
+            machine: 0,
+            exports: [
+                ("NtUserCreateMenu".to_owned(), Some(4)),
+                ("NtUserUnchanged".to_owned(), Some(3)),
+                ("NtUserUnknown".to_owned(), None),
+            ]
+            .into(),
+        };
+
+        let changes = diff(&old, &new);
+
+        assert_eq!(
+            changes,
+            [
+                ExportChange::Removed {
+                    name: "NtUserCallNoParam".to_owned(),
+                    syscall_number: Some(2)
+                },
+                ExportChange::Renumbered {
+                    name: "NtUserCreateMenu".to_owned(),
+                    old: Some(1),
+                    new: Some(4)
+                },
+                ExportChange::Added {
+                    name: "NtUserUnknown".to_owned(),
+                    syscall_number: None
+                },
+            ]
+        );
+
+        assert_eq!(
+            changes[0].affected(),
+            Some(Affected::Syscall("NtUserCallNoParam".to_owned()))
+        );
+        assert_eq!(
+            changes[1].affected(),
+            Some(Affected::Call(NtUserCall::CreateMenu))
+        );
+        assert_eq!(changes[2].affected(), None);
+    }
+}
@@ -1,64 +1,334 @@
 //! Provides error enumerations.
+//!
+//! [`UserCallError`] carries the context the error occurred in, such as the missing library or export, the operating
+//! system build and the Win32 error reported by the loader.
+//!
+//! [`UserCallError::verbose`] captures the backend, operating system build and remediation hints in addition, for
+//! directly surfacing in support logs. Formatting a [`UserCallError`] never queries the operating system.
 
-use std::fmt::Display;
+use core::ffi::CStr;
+use core::fmt::Display;
 
+use crate::version::{Environment, OsBuild};
+#[cfg(feature = "std")]
+use crate::{
+    availability::{win32k_syscalls_disabled, CallerRestriction},
+    capabilities::{mechanism, Mechanism},
+    functions::NtUserCall,
+    version::{os_build, Win10Build},
+};
+
+#[cfg(feature = "std")]
 use windows::{
     core::HRESULT,
     Win32::Foundation::{
-        ERROR_MOD_NOT_FOUND, ERROR_NOT_SUPPORTED, ERROR_OLD_WIN_VERSION, E_ILLEGAL_METHOD_CALL,
+        ERROR_INVALID_DATA, ERROR_MOD_NOT_FOUND, ERROR_NOT_SUPPORTED, ERROR_OLD_WIN_VERSION,
+        E_ILLEGAL_METHOD_CALL, E_INVALIDARG, E_NOTIMPL,
     },
 };
 
-#[repr(usize)]
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UserCallError {
-    OsNotSupported = 1,
-    OsTooNew = 2,
-    CallNotFound = 3,
-    LibraryNotFound = 4,
+    /// The crate does not contain `apfnSimpleCall` indices for `os_build`.
+    OsNotSupported { os_build: OsBuild },
+    /// `os_build` only offers dedicated syscalls.
+    OsTooNew { os_build: OsBuild },
+    /// The function cannot be invoked on the current operating system. `export` is the export that was not found, if
+    /// the function was looked up by name, and `last_error` the error reported by `GetProcAddress`.
+    CallNotFound {
+        export: Option<&'static CStr>,
+        last_error: Option<u32>,
+    },
+    /// `library` has not been loaded. `last_error` is the error reported by `GetModuleHandleW`.
+    LibraryNotFound {
+        library: &'static str,
+        last_error: Option<u32>,
+    },
+    /// The process is running on `environment` instead of Windows.
+    UnsupportedEnvironment { environment: Environment },
+    /// The operation is not implemented for the architecture the crate was compiled for.
+    ArchitectureNotSupported,
+    /// The function takes `expected` parameters, but was invoked with `actual` arguments.
+    InvalidArguments { expected: usize, actual: usize },
+    /// The syscall number of `export` could not be decoded for invoking it directly, neither from its stub in memory
+    /// nor from win32u.dll on disk.
+    SyscallNotDecoded { export: &'static CStr },
 }
 
 impl Display for UserCallError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Self::OsNotSupported => write!(f, "The operating system is not supported."),
-            Self::OsTooNew => write!(
+            Self::OsNotSupported { os_build } => {
+                write!(f, "The operating system {os_build} is not supported.")
+            }
+            Self::OsTooNew { os_build } => write!(
+                f,
+                "The operating system {os_build} does not use the NtUserCall* family of syscalls anymore."
+            ),
+            Self::CallNotFound { export: None, .. } => write!(f, "The function was not found."),
+            Self::CallNotFound {
+                export: Some(export),
+                ..
+            } => write!(f, "The function {} was not found.", export.to_str().unwrap_or_default()),
+            Self::LibraryNotFound { library, .. } => {
+                write!(f, "The library {library} was not found.")
+            }
+            Self::UnsupportedEnvironment { environment } => write!(
                 f,
-                "The operating system does not use the NtUserCall* family of syscalls anymore."
+                "The process is not running on Windows, but on {environment:?}."
             ),
-            Self::CallNotFound => write!(f, "The function was not found."),
-            Self::LibraryNotFound => write!(f, "A required library was not found."),
+            Self::ArchitectureNotSupported => {
+                write!(f, "The operation is not supported on this architecture.")
+            }
+            Self::InvalidArguments { expected, actual } => write!(
+                f,
+                "The function takes {expected} parameters, but {actual} arguments were passed."
+            ),
+            Self::SyscallNotDecoded { export } => write!(
+                f,
+                "The syscall number of {} could not be decoded.",
+                export.to_str().unwrap_or_default()
+            ),
+        }?;
+
+        if let Some(last_error) = self.last_error() {
+            write!(f, " (error {last_error})")?;
         }
+
+        Ok(())
     }
 }
 
-impl TryFrom<usize> for UserCallError {
-    type Error = ();
+impl core::error::Error for UserCallError {}
 
-    fn try_from(value: usize) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Self::OsNotSupported),
-            2 => Ok(Self::OsTooNew),
-            3 => Ok(Self::CallNotFound),
-            4 => Ok(Self::LibraryNotFound),
-            _ => Err(()),
+impl UserCallError {
+    /// Returns the Win32 error reported by the loader, if captured.
+    ///
+    /// The conversion into [`windows::core::Error`] maps by variant and does not include this error.
+    pub fn last_error(&self) -> Option<u32> {
+        match self {
+            Self::CallNotFound { last_error, .. } | Self::LibraryNotFound { last_error, .. } => {
+                *last_error
+            }
+            _ => None,
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl UserCallError {
+    /// Returns this error as it occurred when calling `call`, with the backend, operating system build and
+    /// remediation hints.
+    pub fn verbose(self, call: NtUserCall) -> VerboseError {
+        VerboseError::new(self, Some(call))
+    }
+
+    /// Formats this error as it occurred when calling `call` verbosely. See [`UserCallError::verbose`].
+    pub fn to_verbose_string(self, call: NtUserCall) -> String {
+        self.verbose(call).to_string()
+    }
+}
+
+/// A [`UserCallError`] with the context it occurred in, as returned by [`UserCallError::verbose`].
+///
+/// The context is captured when the value is created.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VerboseError {
+    error: UserCallError,
+    call: Option<NtUserCall>,
+    mechanism: Mechanism,
+    os_build: OsBuild,
+    win32k_disabled: bool,
+}
+
+#[cfg(feature = "std")]
+impl VerboseError {
+    fn new(error: UserCallError, call: Option<NtUserCall>) -> Self {
+        Self {
+            error,
+            call,
+            mechanism: mechanism(),
+            os_build: os_build(),
+            win32k_disabled: win32k_syscalls_disabled(),
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn error(&self) -> UserCallError {
+        self.error
+    }
+
+    /// Returns the function whose call failed, if known.
+    pub fn call(&self) -> Option<NtUserCall> {
+        self.call
+    }
+
+    /// Returns the remediation hints for the error, most specific first.
+    pub fn hints(&self) -> impl Iterator<Item = &'static str> {
+        let error = match self.error {
+            UserCallError::OsNotSupported { .. } if cfg!(any(target_vendor = "win7", feature = "all_os_versions")) => {
+                "Windows 7 or newer is required"
+            }
+            UserCallError::OsNotSupported { .. } => {
+                "Windows 8 or newer is required; enable the `all_os_versions` feature for Windows 7 support"
+            }
+            UserCallError::OsTooNew { .. } => {
+                "this build only offers dedicated win32u.dll exports, not the NtUserCall* family of syscalls"
+            }
+            UserCallError::CallNotFound { .. }
+                if matches!(self.mechanism, Mechanism::DedicatedSyscalls | Mechanism::DirectSyscalls) =>
+            {
+                "win32u.dll does not export this entry on this build"
+            }
+            UserCallError::CallNotFound { .. }
+                if self.os_build.major == 10 && Win10Build::from_build(self.os_build.build) < Win10Build::V1903 =>
+            {
+                "no indices are built in for Windows 10 releases before 1903; supply them via `indices::register_table` \
+                 or register the indices discovered via `indices::discover`"
+            }
+            UserCallError::CallNotFound { .. } => "this entry has no apfnSimpleCall index on this build",
+            UserCallError::LibraryNotFound { .. } => "win32u.dll or user32.dll is not loaded in this process",
+            UserCallError::UnsupportedEnvironment { .. } => {
+                "Wine and ReactOS use their own indices; call the documented user32.dll functions instead"
+            }
+            UserCallError::ArchitectureNotSupported => {
+                "index discovery requires x64; direct syscalls and Windows 7 to 8.1 require x64 or WOW64, as native \
+                 32-bit Windows is not supported"
+            }
+            UserCallError::InvalidArguments { .. } => "pass one argument per parameter, in declaration order",
+            UserCallError::SyscallNotDecoded { .. } => {
+                "the syscall stub has been patched and win32u.dll could not be read; disable direct syscalls to call it"
+            }
+        };
+
+        let restriction = self
+            .call
+            .and_then(NtUserCall::caller_restriction)
+            .map(|restriction| match restriction {
+                CallerRestriction::Csrss => "this entry requires CSRSS",
+                CallerRestriction::Winlogon => "this entry requires winlogon",
+                CallerRestriction::Dwm => "this entry requires the desktop window manager",
+                CallerRestriction::ImmersiveBroker => "this entry requires the immersive broker",
+                CallerRestriction::IamAccess => {
+                    "this entry requires access granted by the immersive application manager"
+                }
+            });
+
+        let win32k_disabled = self
+            .win32k_disabled
+            .then_some("win32k syscalls are disabled by mitigation policy");
+
+        let backend = (self.mechanism == Mechanism::Backend).then_some(
+            "a backend is installed, which may have returned this error instead of the syscall",
+        );
+
+        win32k_disabled
+            .into_iter()
+            .chain(backend)
+            .chain([error])
+            .chain(restriction)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for VerboseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (", self.error)?;
+
+        if let Some(call) = self.call {
+            write!(f, "call: NtUser{}, ", call.name())?;
+        }
+
+        write!(
+            f,
+            "backend: {}, OS build: {}",
+            self.mechanism, self.os_build
+        )?;
+
+        for hint in self.hints() {
+            write!(f, "; hint: {hint}")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerboseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<UserCallError> for windows::core::Error {
     fn from(value: UserCallError) -> Self {
         match value {
-            UserCallError::OsNotSupported => {
+            UserCallError::OsNotSupported { .. } => {
                 Self::from_hresult(HRESULT::from_win32(ERROR_OLD_WIN_VERSION.0))
             }
-            UserCallError::OsTooNew => Self::from_hresult(E_ILLEGAL_METHOD_CALL),
-            UserCallError::CallNotFound => {
+            UserCallError::OsTooNew { .. } => Self::from_hresult(E_ILLEGAL_METHOD_CALL),
+            UserCallError::CallNotFound { .. } => {
                 Self::from_hresult(HRESULT::from_win32(ERROR_NOT_SUPPORTED.0))
             }
-            UserCallError::LibraryNotFound => {
+            UserCallError::LibraryNotFound { .. } => {
                 Self::from_hresult(HRESULT::from_win32(ERROR_MOD_NOT_FOUND.0))
             }
+            UserCallError::UnsupportedEnvironment { .. }
+            | UserCallError::ArchitectureNotSupported => Self::from_hresult(E_NOTIMPL),
+            UserCallError::InvalidArguments { .. } => Self::from_hresult(E_INVALIDARG),
+            UserCallError::SyscallNotDecoded { .. } => {
+                Self::from_hresult(HRESULT::from_win32(ERROR_INVALID_DATA.0))
+            }
         }
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use windows::{core::HRESULT, Win32::Foundation::ERROR_NOT_SUPPORTED};
+
+    use crate::{
+        capabilities::Mechanism,
+        error::{UserCallError, VerboseError},
+        functions::NtUserCall,
+        version::OsBuild,
+    };
+
+    #[test]
+    pub fn verbose_error_should_include_hints() {
+        let error = VerboseError {
+            error: UserCallError::CallNotFound {
+                export: Some(c"NtUserRemoteNotify"),
+                last_error: Some(127),
+            },
+            call: Some(NtUserCall::RemoteNotify),
+            mechanism: Mechanism::DedicatedSyscalls,
+            os_build: OsBuild {
+                major: 10,
+                minor: 0,
+                build: 22631,
+            },
+            win32k_disabled: true,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "The function NtUserRemoteNotify was not found. (error 127) (call: NtUserRemoteNotify, backend: dedicated win32u.dll syscalls, \
+             OS build: 10.0.22631; hint: win32k syscalls are disabled by mitigation policy; \
+             hint: win32u.dll does not export this entry on this build; hint: this entry requires CSRSS)"
+        );
+    }
+
+    #[test]
+    pub fn hresult_should_map_by_variant() {
+        let error = windows::core::Error::from(UserCallError::CallNotFound {
+            export: Some(c"NtUserRemoteNotify"),
+            last_error: Some(127),
+        });
+
+        assert_eq!(error.code(), HRESULT::from_win32(ERROR_NOT_SUPPORTED.0));
+    }
+}
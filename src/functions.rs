@@ -5,44 +5,80 @@
 //! - On older operating systems the function is invoked via the `NtUserCall*` family of syscalls, loaded from `win32u.dll`.
 //! - On Windows 7 to 8.1, `NtUserCall*` syscalls are not exported, and the syscalls are invoked directly via inline assembly.
 //!
-//! Function resolution happens the first time the function is called.
+//! Function resolution happens the first time the function is called, and its outcome is cached for subsequent calls.
+//! [`prewarm`] resolves all functions up front instead.
+//!
+//! With one of the `pin-win7`, `pin-win8`, `pin-win81` or `pin-win10` features enabled, the operating system version is
+//! not detected and the `apfnSimpleCall` indices of the pinned version are resolved at compile time, which removes the
+//! per-call resolution of the functions except for the `NtUserCall*` syscall itself. Binaries built with a pinned version
+//! invoke the wrong functions on any other version, which [`prewarm`] verifies. Binaries targeting the `win7` vendor
+//! still run on newer versions, so they are not pinned implicitly.
+//!
+//! With the `win11-only` feature enabled, the functions are imported from `win32u.dll` as regular imports instead,
+//! which removes all runtime resolution. As the loader resolves those imports when the binary is loaded, binaries
+//! built with this feature fail to load on older operating systems or builds lacking any referenced export. On MSVC,
+//! linking the final binary with `/DELAYLOAD:win32u.dll` and `delayimp.lib` defers resolution to the first call of
+//! each function instead.
 //!
 //! Errors:
 //! - [`UserCallError::OsNotSupported`]: The crate does not contain table entry indices.
 //! - [`UserCallError::LibraryNotFound`]: A required DLL has not been loaded.
 //! - [`UserCallError::CallNotFound`]: The function cannot be invoked on the current operating system.
+//!
+//! With the `hresult-api` feature enabled, `hresult` provides the same functions returning [`windows::core::Result`].
+//!
+//! With the `direct-syscalls` feature enabled, syscalls exported from `win32u.dll` are invoked directly via the syscall
+//! number decoded from their stub on x86 and x64, see [`crate::context::ContextConfig::direct_syscalls`]. The feature
+//! compiles on ARM64, but functions then fail with [`UserCallError::ArchitectureNotSupported`].
+//!
+//! With the `backend` feature enabled, the functions dispatch to a backend installed via `crate::backend` instead of
+//! invoking the syscalls, if any.
+//!
+//! Without the `std` feature, only [`NtUserCall`] is available, whose functions are invoked via [`crate::raw::call`].
 
+use core::ffi::CStr;
+#[cfg(feature = "std")]
 use std::ffi::c_void;
-use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
 
+#[cfg(feature = "std")]
 use windows::Win32::Devices::Display::HDEV;
+#[cfg(feature = "std")]
 use windows::Win32::Graphics::Gdi::HMONITOR;
+#[cfg(feature = "std")]
 use windows::Win32::UI::Input::KeyboardAndMouse::HKL;
+#[cfg(feature = "std")]
 use windows::Win32::UI::WindowsAndMessaging::MESSAGEBOX_STYLE;
-use windows::{
-    core::{w, PCSTR},
-    Win32::{
-        Foundation::{
-            BOOL, HANDLE, HWND, LPARAM, LRESULT, NTSTATUS, POINT, UNICODE_STRING, WPARAM,
-        },
-        Graphics::Gdi::{HDC, HRGN},
-        System::{
-            LibraryLoader::{GetModuleHandleW, GetProcAddress},
-            StationsAndDesktops::HDESK,
-        },
-        UI::WindowsAndMessaging::{GET_CLASS_LONG_INDEX, HDWP, HICON, HMENU, SYSTEM_METRICS_INDEX},
-    },
+#[cfg(feature = "std")]
+use windows::Win32::{
+    Foundation::{BOOL, HANDLE, HWND, LPARAM, LRESULT, NTSTATUS, POINT, UNICODE_STRING, WPARAM},
+    Graphics::Gdi::{HDC, HRGN},
+    System::StationsAndDesktops::HDESK,
+    UI::WindowsAndMessaging::{GET_CLASS_LONG_INDEX, HDWP, HICON, HMENU, SYSTEM_METRICS_INDEX},
 };
 
+#[cfg(feature = "std")]
+use crate::context::Context;
+#[cfg(feature = "instrumentation")]
+use crate::instrumentation::CallOutcome;
 use crate::{
     error::UserCallError,
-    indices::get_index,
-    version::{get_os_version, has_dedicated_syscalls, OsVersion},
+    resolution::{
+        find_export, resolve_stub, resolved, resolved_family, FamilyDispatch, Library, Resolution,
+    },
+    version::{
+        detect_os_version, get_os_version, has_dedicated_syscalls, os_build, OsVersion,
+        PINNED_OS_VERSION,
+    },
 };
+#[cfg(all(feature = "std", not(feature = "win11-only")))]
+use crate::{indices::pinned_index, resolution::index_resolution};
+
+#[cfg(feature = "std")]
 trait IntoCallParam {
     fn into_call_param(self) -> usize;
 }
 
+#[cfg(feature = "std")]
 macro_rules! into_call_param_self_as {
     ($($type:ty),+) => {
         $(
@@ -55,6 +91,7 @@ macro_rules! into_call_param_self_as {
     };
 }
 
+#[cfg(feature = "std")]
 macro_rules! into_call_param_self_0_as {
     ($($type:ty),+) => {
         $(
@@ -67,6 +104,7 @@ macro_rules! into_call_param_self_0_as {
     };
 }
 
+#[cfg(feature = "std")]
 macro_rules! into_call_param_transmute {
     ($($type:ty),+) => {
         $(
@@ -82,19 +120,23 @@ macro_rules! into_call_param_transmute {
     };
 }
 
+#[cfg(feature = "std")]
 impl<T> IntoCallParam for *const T {
     fn into_call_param(self) -> usize {
         self as _
     }
 }
 
+#[cfg(feature = "std")]
 impl<T> IntoCallParam for *mut T {
     fn into_call_param(self) -> usize {
         self as _
     }
 }
 
+#[cfg(feature = "std")]
 into_call_param_self_as!(i16, i32, u32, usize);
+#[cfg(feature = "std")]
 into_call_param_self_0_as!(
     BOOL,
     GET_CLASS_LONG_INDEX,
@@ -104,12 +146,15 @@ into_call_param_self_0_as!(
     SYSTEM_METRICS_INDEX,
     WPARAM
 );
+#[cfg(feature = "std")]
 into_call_param_transmute!(HANDLE, HDC, HDESK, HDEV, HRGN, HWND);
 
+#[cfg(feature = "std")]
 trait FromCallReturn {
     fn from_call_return(value: usize) -> Self;
 }
 
+#[cfg(feature = "std")]
 macro_rules! from_call_return_as {
     ($($type:ty),+) => {
         $(
@@ -122,6 +167,7 @@ macro_rules! from_call_return_as {
     };
 }
 
+#[cfg(feature = "std")]
 macro_rules! from_call_return_self {
     ($($type:ty),+) => {
         $(
@@ -134,37 +180,226 @@ macro_rules! from_call_return_self {
     };
 }
 
+#[cfg(feature = "std")]
 impl FromCallReturn for () {
     fn from_call_return(_value: usize) -> Self {}
 }
 
+#[cfg(feature = "std")]
 impl<T> FromCallReturn for *const T {
     fn from_call_return(value: usize) -> Self {
         value as _
     }
 }
 
+#[cfg(feature = "std")]
 impl<T> FromCallReturn for *mut T {
     fn from_call_return(value: usize) -> Self {
         value as _
     }
 }
 
+#[cfg(feature = "std")]
 from_call_return_as!(i32, u32, usize);
+#[cfg(feature = "std")]
 from_call_return_self!(
     BOOL, HANDLE, HDESK, HDWP, HICON, HKL, HMENU, HMONITOR, HWND, LPARAM, LRESULT, NTSTATUS
 );
 
+#[cfg(feature = "instrumentation")]
+macro_rules! call_outcome_as {
+    ($($type:ty),+) => {
+        $(
+        impl CallOutcome for $type {
+            fn call_outcome(&self) -> Result<usize, UserCallError> {
+                Ok(*self as _)
+            }
+        }
+    )+
+    };
+}
+
+#[cfg(feature = "instrumentation")]
+macro_rules! call_outcome_self_0_as {
+    ($($type:ty),+) => {
+        $(
+        impl CallOutcome for $type {
+            fn call_outcome(&self) -> Result<usize, UserCallError> {
+                Ok(self.0 as _)
+            }
+        }
+    )+
+    };
+}
+
+#[cfg(feature = "instrumentation")]
+impl CallOutcome for () {
+    fn call_outcome(&self) -> Result<usize, UserCallError> {
+        Ok(0)
+    }
+}
+
+#[cfg(feature = "instrumentation")]
+impl<T> CallOutcome for *const T {
+    fn call_outcome(&self) -> Result<usize, UserCallError> {
+        Ok(*self as _)
+    }
+}
+
+#[cfg(feature = "instrumentation")]
+impl<T> CallOutcome for *mut T {
+    fn call_outcome(&self) -> Result<usize, UserCallError> {
+        Ok(*self as _)
+    }
+}
+
+#[cfg(feature = "instrumentation")]
+impl<T: CallOutcome> CallOutcome for Result<T, UserCallError> {
+    fn call_outcome(&self) -> Result<usize, UserCallError> {
+        self.as_ref().map_err(|error| *error)?.call_outcome()
+    }
+}
+
+#[cfg(feature = "instrumentation")]
+call_outcome_as!(i32, u32, usize);
+#[cfg(feature = "instrumentation")]
+call_outcome_self_0_as!(
+    BOOL, HANDLE, HDESK, HDWP, HICON, HKL, HMENU, HMONITOR, HWND, LPARAM, LRESULT, NTSTATUS
+);
+
+#[cfg(feature = "std")]
 macro_rules! nt_user_call_fn_body {
-    ( $syscall:ident $call:ident ) => {{
-        user_call::$syscall($call)
+    ( [$($prefix:tt)*] ($family:expr) $syscall:ident $call:ident ) => {{
+        $($prefix)* $syscall($family, $call)
     }};
 
-    ( $syscall:ident $call:ident $($paramname:ident)* ) => {{
-        user_call::$syscall($(IntoCallParam::into_call_param($paramname)),*, $call)
+    ( [$($prefix:tt)*] ($family:expr) $syscall:ident $call:ident $($paramname:ident)* ) => {{
+        $($prefix)* $syscall($family, $(IntoCallParam::into_call_param($paramname)),*, $call)
     }};
 }
 
+/// Evaluates `$body` as a closure reporting the call to [`crate::instrumentation`] as the given
+/// [`crate::instrumentation::Callee`]. `$body` records how the call is dispatched in `$dispatch` via `dispatched!`.
+#[cfg(feature = "instrumentation")]
+macro_rules! instrumented {
+    ( $callee:ident $fields:tt, |$dispatch:ident| $body:block ) => {
+        crate::instrumentation::instrument(
+            crate::instrumentation::Callee::$callee $fields,
+            |$dispatch: &mut crate::instrumentation::Dispatch| $body,
+        )
+    };
+}
+
+/// Evaluates `$body` as is, which must be the entire function body.
+#[cfg(not(feature = "instrumentation"))]
+macro_rules! instrumented {
+    ( $callee:ident $fields:tt, |$dispatch:ident| $body:block ) => {
+        $body
+    };
+}
+
+/// Records how the call is dispatched in the `$dispatch` of the enclosing `instrumented!`.
+#[cfg(feature = "instrumentation")]
+macro_rules! dispatched {
+    ( $dispatch:ident = $($variant:tt)+ ) => {
+        *$dispatch = crate::instrumentation::Dispatch::$($variant)+;
+    };
+}
+
+#[cfg(not(feature = "instrumentation"))]
+macro_rules! dispatched {
+    ( $dispatch:ident = $($variant:tt)+ ) => {};
+}
+
+pub(crate) use {dispatched, instrumented};
+
+#[cfg(feature = "backend")]
+macro_rules! overridden {
+    ( $dispatch:ident, $name:ident ($($paramname:ident),*) ) => {
+        // SAFETY: The caller upholds the safety requirements of the function, which are forwarded to the backend.
+        if let Some(result) = unsafe {
+            crate::backend::dispatch_override(NtUserCall::$name, || [$(IntoCallParam::into_call_param($paramname)),*])
+        } {
+            dispatched!($dispatch = Backend);
+            return result.map(FromCallReturn::from_call_return);
+        }
+    };
+}
+
+#[cfg(all(feature = "std", not(feature = "backend")))]
+macro_rules! overridden {
+    ( $dispatch:ident, $name:ident ($($paramname:ident),*) ) => {};
+}
+
+/// Invokes the function as resolved to `$resolution`.
+#[cfg(feature = "std")]
+macro_rules! nt_user_call_resolved_body {
+    (
+        $dispatch:ident, $resolution:expr => $syscall:ident $call:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
+    ) => {
+        match $resolution {
+            Resolution::Function(function) => {
+                dispatched!($dispatch = DedicatedSyscall);
+
+                // SAFETY: The function pointer has been resolved from the dedicated syscall export matching this signature.
+                let function: unsafe extern "system" fn($($paramtype),*) -> $rettype = unsafe {
+                    std::mem::transmute(function)
+                };
+
+                // SAFETY: `function` is a valid function pointer.
+                Ok(unsafe { function($($paramname),*) })
+            }
+            Resolution::Index(index, family) => {
+                dispatched!($dispatch = UserCall { index: index as u32 });
+
+                let $call = index as u32;
+
+                nt_user_call_fn_body!([user_call::dispatched::] (family) $syscall $call $($paramname)*)
+                    .map(FromCallReturn::from_call_return)
+            }
+            Resolution::Syscall(number) => {
+                dispatched!($dispatch = DirectSyscall { number });
+
+                // SAFETY: The syscall number has been decoded from the dedicated syscall export matching this signature.
+                Ok(<$rettype as FromCallReturn>::from_call_return(unsafe {
+                    direct_syscall(number, [$(IntoCallParam::into_call_param($paramname)),*])
+                }))
+            }
+            Resolution::Error(error) => Err(error),
+        }
+    };
+}
+
+#[cfg(feature = "win11-only")]
+macro_rules! nt_user_call_fn {
+    (
+        #[doc = $doc:literal] $syscall:ident $call:ident $vis:vis fn $name:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
+    ) => {
+        paste::paste! {
+            #[doc = $doc]
+            #[allow(clippy::empty_docs, clippy::missing_safety_doc)]
+            #[expect(non_snake_case)]
+            $vis unsafe fn [< NtUser $name >] ($($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
+                // win32u.dll exports the syscalls by their undecorated names, even though they are `stdcall` on x86.
+                #[cfg_attr(target_arch = "x86", link(name = "win32u", kind = "raw-dylib", import_name_type = "undecorated"))]
+                #[cfg_attr(not(target_arch = "x86"), link(name = "win32u", kind = "raw-dylib"))]
+                extern "system" {
+                    #[link_name = concat!("NtUser", stringify!($name))]
+                    fn import($($paramname: $paramtype),*) -> $rettype;
+                }
+
+                instrumented!(Function(NtUserCall::$name), |dispatch| {
+                    overridden!(dispatch, $name ($($paramname),*));
+
+                    dispatched!(dispatch = DedicatedSyscall);
+                    Ok(import($($paramname),*))
+                })
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "std", not(feature = "win11-only")))]
 macro_rules! nt_user_call_fn {
     (
         #[doc = $doc:literal] $syscall:ident $call:ident $vis:vis fn $name:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
@@ -174,31 +409,56 @@ macro_rules! nt_user_call_fn {
             #[allow(clippy::empty_docs, clippy::missing_safety_doc)]
             #[expect(non_snake_case)]
             $vis unsafe fn [< NtUser $name >] ($($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
-                if has_dedicated_syscalls() {
-                    // Starting with Windows 11, NtUserCall* has been replaced with dedicated syscalls in win32u.
-                    crate::macros::load_runtime_fn_body!(["win32u"] $name($($paramname: $paramtype),*) -> $rettype)
-                } else {
-                    static CALL_ATOMIC: AtomicU32 = AtomicU32::new(u16::MAX as u32 + 1);
-
-                    let call_index = match CALL_ATOMIC.load(Ordering::Relaxed) {
-                        index@..=0xFFFFu32 => index,
-                        u32::MAX => return Err(UserCallError::CallNotFound),
-                        _ => match get_index(NtUserCall::$name) {
-                            Some(index) => {
-                                CALL_ATOMIC.store(index as _, Ordering::SeqCst);
-                                index as _
-                            },
-                            None => {
-                                CALL_ATOMIC.store(u32::MAX, Ordering::SeqCst);
-                                return Err(UserCallError::CallNotFound);
-                            }
+                instrumented!(Function(NtUserCall::$name), |dispatch| {
+                    overridden!(dispatch, $name ($($paramname),*));
+
+                    let resolution = if PINNED_OS_VERSION.is_some() {
+                        const INDEX: Option<u16> = pinned_index(NtUserCall::$name);
+
+                        match INDEX {
+                            Some(index) => index_resolution(index, resolved_family(Family::$syscall)),
+                            None => Resolution::Error(UserCallError::CallNotFound { export: None, last_error: None }),
                         }
+                    } else {
+                        resolved(NtUserCall::$name)
                     };
 
-                    let $call = call_index;
+                    nt_user_call_resolved_body!(dispatch, resolution => $syscall $call ($($paramname: $paramtype),*) -> $rettype)
+                })
+            }
+        }
+    };
+}
 
-                    nt_user_call_fn_body!($syscall $call $($paramname)*).map(FromCallReturn::from_call_return)
-                }
+#[cfg(feature = "std")]
+macro_rules! nt_user_call_context_fn {
+    (
+        #[doc = $doc:literal] $syscall:ident $call:ident $vis:vis fn $name:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
+    ) => {
+        paste::paste! {
+            #[doc = $doc]
+            #[allow(clippy::empty_docs, clippy::missing_safety_doc)]
+            #[expect(non_snake_case)]
+            $vis unsafe fn [< NtUser $name >] (&self, $($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
+                instrumented!(Function(NtUserCall::$name), |dispatch| {
+                    nt_user_call_resolved_body!(dispatch, self.resolve(NtUserCall::$name) => $syscall $call ($($paramname: $paramtype),*) -> $rettype)
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "hresult-api")]
+macro_rules! nt_user_call_hresult_fn {
+    (
+        #[doc = $doc:literal] $vis:vis fn $name:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
+    ) => {
+        paste::paste! {
+            #[doc = $doc]
+            #[allow(clippy::empty_docs, clippy::missing_safety_doc)]
+            #[expect(non_snake_case)]
+            $vis unsafe fn [< NtUser $name >] ($($paramname: $paramtype),*) -> windows::core::Result<$rettype> {
+                super::[< NtUser $name >]($($paramname),*).map_err(Into::into)
             }
         }
     };
@@ -209,11 +469,68 @@ macro_rules! nt_user_call {
         #[doc = $enumdoc]
         #[allow(non_camel_case_types)]
         #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub enum NtUserCall {
             $($name),+
         }
 
-        $(nt_user_call_fn! { #[doc = $doc] $syscall CALL $vis fn $name ($($funcdef)*) -> $rettype })+
+        impl NtUserCall {
+            pub(crate) const COUNT: usize = [$(stringify!($name)),+].len();
+
+            /// All functions, in declaration order.
+            pub const ALL: [Self; Self::COUNT] = [$(Self::$name),+];
+
+            /// Returns the function with the given name, without the `NtUser` prefix.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(Self::$name),)+
+                    _ => None,
+                }
+            }
+
+            /// Returns the name of the function without the `NtUser` prefix.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name)),+
+                }
+            }
+
+            /// Returns the `NtUserCall*` syscall this function is invoked with prior to Windows 11.
+            pub(crate) const fn family(self) -> Family {
+                match self {
+                    $(Self::$name => Family::$syscall),+
+                }
+            }
+
+            /// Returns the name of the dedicated syscall exported from win32u.dll on Windows 11 or newer.
+            pub(crate) const fn export_name(self) -> &'static CStr {
+                let name = match self {
+                    $(Self::$name => concat!("NtUser", stringify!($name), "\u{0}")),+
+                };
+
+                // SAFETY: `name` is null-terminated and contains no other null bytes.
+                unsafe { CStr::from_bytes_with_nul_unchecked(name.as_bytes()) }
+            }
+        }
+
+        $(
+            #[cfg(feature = "std")]
+            nt_user_call_fn! { #[doc = $doc] $syscall CALL $vis fn $name ($($funcdef)*) -> $rettype }
+        )+
+
+        #[cfg(feature = "std")]
+        impl Context {
+            $(nt_user_call_context_fn! { #[doc = $doc] $syscall CALL $vis fn $name ($($funcdef)*) -> $rettype })+
+        }
+
+        /// Provides the functions of [`crate::functions`] returning [`windows::core::Result`], with [`UserCallError`]
+        /// converted into the corresponding [`windows::core::Error`].
+        #[cfg(feature = "hresult-api")]
+        pub mod hresult {
+            use super::*;
+
+            $(nt_user_call_hresult_fn! { #[doc = $doc] $vis fn $name ($($funcdef)*) -> $rettype })+
+        }
     };
 }
 
@@ -711,12 +1028,121 @@ nt_user_call! {
     NtUserCallTwoParam pub fn ScaleSystemMetricForDPIWithoutCache(metric: SYSTEM_METRICS_INDEX, dpi: u32) -> i32;
 }
 
+/// Variants of the functions taking pointers to out-parameters or buffers, taking references or slices instead.
+///
+/// Every function taking pointers has a variant here, except [`NtUserMapDesktopObject`], whose parameter is a
+/// handle. Untyped buffers are taken as `&mut T` or `&T`, where `T` must match the layout expected by the kernel.
+///
+/// The functions remain `unsafe`, as the kernel-side semantics of most entries are undocumented. The raw variants in
+/// [`crate::functions`] remain available for exotic uses, e.g. passing null pointers. Wrappers returning the written
+/// values, for the entries that are sound to call, are provided by [`crate::safe`].
+#[cfg(feature = "std")]
+pub mod refs {
+    use super::{c_void, UserCallError, BOOL, HDEV, NTSTATUS, POINT, UNICODE_STRING};
+
+    /// See [`super::NtUserGetProcessDefaultLayout`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserGetProcessDefaultLayout(layout: &mut u32) -> Result<BOOL, UserCallError> {
+        super::NtUserGetProcessDefaultLayout(layout)
+    }
+
+    /// See [`super::NtUserGetWinStationInfo`]. `T` must match the layout of the structure expected by the kernel.
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserGetWinStationInfo<T>(info: &mut T) -> Result<BOOL, UserCallError> {
+        super::NtUserGetWinStationInfo(info as *mut T as *mut c_void)
+    }
+
+    /// See [`super::NtUserGetCursorPos`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserGetCursorPos(point: &mut POINT, which: u32) -> Result<BOOL, UserCallError> {
+        super::NtUserGetCursorPos(point, which)
+    }
+
+    /// See [`super::NtUserGetHDevName`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserGetHDevName(
+        hdev: HDEV,
+        buffer: &mut [u8; 64],
+    ) -> Result<BOOL, UserCallError> {
+        super::NtUserGetHDevName(hdev, buffer)
+    }
+
+    /// See [`super::NtUserSetWatermarkStrings`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserSetWatermarkStrings(
+        strings: &UNICODE_STRING,
+    ) -> Result<BOOL, UserCallError> {
+        super::NtUserSetWatermarkStrings(strings)
+    }
+
+    /// See [`super::NtUserRemoteNotify`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserRemoteNotify(unknown: &u32) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserRemoteNotify(unknown)
+    }
+
+    /// See [`super::NtUserRemoteShadowCleanup`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserRemoteShadowCleanup(buffer: &[u8]) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserRemoteShadowCleanup(buffer.as_ptr() as *const c_void, buffer.len())
+    }
+
+    /// See [`super::NtUserRemoteShadowStart`].
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserRemoteShadowStart(buffer: &[u8]) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserRemoteShadowStart(buffer.as_ptr() as *const c_void, buffer.len())
+    }
+
+    /// See [`super::NtUserRemoteReconnect`]. `T` must match the layout of the structure expected by the kernel.
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserRemoteReconnect<T>(unknown: &mut T) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserRemoteReconnect(unknown as *mut T as *mut c_void)
+    }
+
+    /// See [`super::NtUserRemoteThinwireStats`]. `T` must match the layout of the structure expected by the kernel.
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserRemoteThinwireStats<T>(stats: &mut T) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserRemoteThinwireStats(stats as *mut T as *mut c_void)
+    }
+
+    /// See [`super::NtUserInitAnsiOem`]. `T` and `U` must match the layouts of the buffers expected by the kernel.
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserInitAnsiOem<T, U>(
+        param1: &mut T,
+        param2: &mut U,
+    ) -> Result<BOOL, UserCallError> {
+        super::NtUserInitAnsiOem(
+            param1 as *mut T as *mut c_void,
+            param2 as *mut U as *mut c_void,
+        )
+    }
+
+    /// See [`super::NtUserCitSetInfo`]. `T` must match the layout of the structure expected by the kernel for `which`.
+    #[allow(clippy::missing_safety_doc)]
+    #[expect(non_snake_case)]
+    pub unsafe fn NtUserCitSetInfo<T>(which: u32, info: &mut T) -> Result<NTSTATUS, UserCallError> {
+        super::NtUserCitSetInfo(which, info as *mut T as *mut c_void)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
 macro_rules! nt_user_call_syscall_fn {
         (($paramname:ident: $paramtype:ty) -> $rettype:ty) => {
             unsafe extern "system" fn syscall<const SYSCALL_NR: usize>(
                 $paramname: $paramtype
             ) -> $rettype {
-                use std::arch::asm;
+                use core::arch::asm;
                 let result;
 
                 asm!(
@@ -737,7 +1163,7 @@ macro_rules! nt_user_call_syscall_fn {
                 $paramname: $paramtype,
                 $param2name: $param2type,
             ) -> $rettype {
-                use std::arch::asm;
+                use core::arch::asm;
                 let result;
 
                 asm!(
@@ -760,7 +1186,7 @@ macro_rules! nt_user_call_syscall_fn {
                 $param2name: $param2type,
                 $param3name: $param3type,
             ) -> $rettype {
-                use std::arch::asm;
+                use core::arch::asm;
                 let result;
 
                 asm!(
@@ -779,120 +1205,355 @@ macro_rules! nt_user_call_syscall_fn {
         };
     }
 
+#[cfg(target_arch = "x86")]
+macro_rules! nt_user_call_syscall_fn {
+        (($($paramname:ident: $paramtype:ty),+) -> $rettype:ty) => {
+            nt_user_call_syscall_fn!(@stub syscall "", ""; ($($paramname: $paramtype),+) -> $rettype);
+            // Windows 7 passes the turbo thunk for converting the arguments in `ecx`. No thunk selectors are built in,
+            // so 0 selects the generic conversion via the argument descriptors of the service table.
+            #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))]
+            nt_user_call_syscall_fn!(@stub syscall_win7 "xor ecx, ecx\nlea edx, [esp + 4]", "add esp, 4"; ($($paramname: $paramtype),+) -> $rettype);
+        };
+
+        // Emulates the WOW64 syscall stubs of user32.dll: The arguments are pushed and a local stub is called, which
+        // enters the WOW64 transition via `WOW32Reserved` in the TEB and returns via `ret` as a stdcall function.
+        (@stub $stub:ident $setup:literal, $cleanup:literal; ($($paramname:ident: $paramtype:ty),+) -> $rettype:ty) => {
+            unsafe extern "system" fn $stub<const SYSCALL_NR: usize>(
+                $($paramname: $paramtype),+
+            ) -> $rettype {
+                use core::arch::asm;
+                const COUNT: usize = [$(stringify!($paramname)),+].len();
+
+                let args = [$($paramname as usize),+];
+                let result;
+
+                asm!(
+                    "mov ecx, {count}",
+                    "2:",
+                    "push dword ptr [{args} + ecx * 4 - 4]",
+                    "loop 2b",
+                    "call 3f",
+                    "jmp 4f",
+                    "3:",
+                    "mov eax, {syscall_nr}",
+                    $setup,
+                    "call dword ptr fs:[0xC0]",
+                    $cleanup,
+                    "ret {size}",
+                    "4:",
+                    args = in(reg) args.as_ptr(),
+                    count = const(COUNT),
+                    size = const(COUNT * 4),
+                    syscall_nr = const(SYSCALL_NR),
+                    lateout("eax") result,
+                    out("ecx") _,
+                    out("edx") _,
+                    );
+
+                result
+            }
+        };
+    }
+
+/// Selects the direct syscall stub for an operating system version.
+#[cfg(target_arch = "x86_64")]
+macro_rules! nt_user_call_syscall_stub {
+    ($os:ident $syscall_nr:literal) => {
+        syscall::<$syscall_nr>
+    };
+}
+
+/// Selects the direct syscall stub for an operating system version.
+///
+/// The syscall numbers are those of the x64 kernel, so only WOW64 processes are supported.
+#[cfg(target_arch = "x86")]
+macro_rules! nt_user_call_syscall_stub {
+    (Win7 $syscall_nr:literal) => {{
+        if !is_wow64() {
+            return Resolution::Error(UserCallError::ArchitectureNotSupported);
+        }
+
+        syscall_win7::<$syscall_nr>
+    }};
+
+    ($os:ident $syscall_nr:literal) => {{
+        if !is_wow64() {
+            return Resolution::Error(UserCallError::ArchitectureNotSupported);
+        }
+
+        syscall::<$syscall_nr>
+    }};
+}
+
+/// Resolves a syscall not exported on the running operating system to its built-in syscall stub, if any.
+///
+/// Windows on ARM64 starts with Windows 10, which exports all syscalls, so no stubs are built in for ARM64.
+#[cfg(target_arch = "aarch64")]
+macro_rules! nt_user_call_alternate {
+    ($name:ident $os_version:ident => $($(#[$cfg:meta])? $os:ident = $syscall_nr:literal),* => $rettype:ty => $($paramname:ident: $paramtype:ty),*) => {{
+        _ = $os_version;
+        Resolution::Error(UserCallError::CallNotFound {
+            export: None,
+            last_error: None,
+        })
+    }};
+}
+
+/// Resolves a syscall not exported on the running operating system to its built-in syscall stub, if any.
+#[cfg(not(target_arch = "aarch64"))]
 macro_rules! nt_user_call_alternate {
-        ($name:ident => => $rettype:ty => $($paramname:ident: $paramtype:ty),*) => {{
-            _ = FUNCTION.compare_exchange(
-                std::ptr::null_mut(),
-                UserCallError::CallNotFound as _,
-                Ordering::SeqCst,
-                Ordering::Relaxed,
-            );
-            return Err(UserCallError::CallNotFound);
+        ($name:ident $os_version:ident => => $rettype:ty => $($paramname:ident: $paramtype:ty),*) => {{
+            Resolution::Error(UserCallError::CallNotFound { export: None, last_error: None })
         }};
 
-        ($name:ident => $($(#[$cfg:meta])? $os:ident = $syscall_nr:literal),+ => $rettype:ty => $($paramname:ident: $paramtype:ty),*) => {{
-            println!(concat!("Function ", stringify!($name), " direct syscall"));
+        ($name:ident $os_version:ident => $($(#[$cfg:meta])? $os:ident = $syscall_nr:literal),+ => $rettype:ty => $($paramname:ident: $paramtype:ty),*) => {{
+            #[cfg(feature = "tracing")]
+            tracing::debug!(family = stringify!($name), "using direct syscall stub");
 
             nt_user_call_syscall_fn!(($($paramname: $paramtype),+) -> $rettype);
 
-            let syscall: unsafe extern "system" fn($($paramtype),*) -> $rettype = match get_os_version() {
+            let syscall: unsafe extern "system" fn($($paramtype),*) -> $rettype = match $os_version {
                 $(
                     $(#[$cfg])?
-                    Ok(OsVersion::$os) => syscall::<$syscall_nr>,
+                    Ok(OsVersion::$os) => nt_user_call_syscall_stub!($os $syscall_nr),
                 )+
-                Ok(_) => {
-                    _ = FUNCTION.compare_exchange(
-                        std::ptr::null_mut(),
-                        UserCallError::OsNotSupported as usize as _,
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    );
+                Ok(_) => return Resolution::Error(UserCallError::OsNotSupported { os_build: crate::version::os_build() }),
+                Err(err) => return Resolution::Error(err),
+            };
 
-                    return Err(UserCallError::OsNotSupported);
-                },
-                Err(err) => {
-                    _ = FUNCTION.compare_exchange(
-                        std::ptr::null_mut(),
-                        err as usize as _,
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    );
+            Resolution::Function(syscall as _)
+        }};
+    }
 
-                    return Err(err);
-                },
+macro_rules! nt_user_call_syscall_body {
+        ($resolution:expr => ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty) => {{
+            let function = match $resolution {
+                Resolution::Function(function) => function,
+                // SAFETY: The syscall number has been decoded from the export matching the syscall signature.
+                Resolution::Syscall(number) => return Ok(unsafe { direct_syscall(number, [$($paramname as usize),*]) } as $rettype),
+                Resolution::Error(error) => return Err(error),
+                Resolution::Index(..) => unreachable!("syscall families are never resolved to an index"),
             };
 
-            syscall as _
+            // SAFETY: The function pointer has been resolved from the export or syscall stub matching the syscall signature.
+            let function: unsafe extern "system" fn($($paramtype),*) -> $rettype = unsafe {
+                core::mem::transmute(function)
+            };
+
+            // SAFETY: `function` is a valid function.
+            Ok(unsafe { function($($paramname),*) })
         }};
     }
 
-macro_rules! nt_user_call_syscall {
+macro_rules! nt_user_call_syscalls {
         (
-            $vis:vis fn $name:ident  ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty $(=> $($(#[$cfg:meta])? $os:ident = $syscall_nr:literal),+)?
+            $($vis:vis fn $name:ident  ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty $(=> $($(#[$cfg:meta])? $os:ident = $syscall_nr:literal),+)?;)+
         ) => {
-            #[expect(non_snake_case, clippy::missing_safety_doc)]
-            $vis unsafe fn $name($($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
-                type Function = unsafe extern "system" fn($($paramtype),*) -> $rettype;
+            /// The `NtUserCall*` family of syscalls.
+            #[allow(clippy::enum_variant_names)]
+            #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+            pub(crate) enum Family {
+                $($name),+
+            }
 
-                static FUNCTION: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+            impl Family {
+                pub(crate) const COUNT: usize = [$(stringify!($name)),+].len();
 
-                let mut ptr = FUNCTION.load(Ordering::Relaxed);
+                /// All syscalls, in declaration order.
+                pub(crate) const ALL: [Self; Self::COUNT] = [$(Self::$name),+];
 
-                if ptr.is_null() {
-                    // SAFETY:
-                    let library = match unsafe { GetModuleHandleW(w!("win32u")).or_else(|_| GetModuleHandleW(w!("user32")))  } {
-                        Ok(library) => library,
-                        Err(_) => {
-                            _ = FUNCTION.compare_exchange(std::ptr::null_mut(), 0x1 as _, Ordering::AcqRel, Ordering::Acquire);
-                            return Err(UserCallError::LibraryNotFound);
-                        }
+                /// Returns the position of the `apfnSimpleCall` index among the parameters of this syscall, which equals
+                /// the parameter count of the functions invoked with it.
+                pub(crate) const fn index_parameter(self) -> usize {
+                    match self {
+                        $(Self::$name => [$(stringify!($paramname)),*].len() - 1),+
+                    }
+                }
+
+                /// Returns the syscall with the given name.
+                #[cfg_attr(not(feature = "devtools"), expect(dead_code))]
+                pub(crate) fn from_name(name: &str) -> Option<Self> {
+                    match name {
+                        $(stringify!($name) => Some(Self::$name),)+
+                        _ => None,
+                    }
+                }
+
+                /// Invokes this syscall as resolved to `family` with `args`, which must match its parameters.
+                pub(crate) unsafe fn call(self, family: FamilyDispatch, args: &[usize]) -> Result<usize, UserCallError> {
+                    match self {
+                        $(Self::$name => match *args {
+                            [$($paramname),*] => dispatched::$name(family, $($paramname as _),*),
+                            _ => Err(UserCallError::InvalidArguments {
+                                expected: [$(stringify!($paramname)),*].len(),
+                                actual: args.len(),
+                            }),
+                        }),+
+                    }
+                }
+
+                /// Resolves the export or, if not exported, the direct syscall stub of this syscall. Exports from win32u.dll
+                /// are invoked directly via their syscall number if `direct_syscalls`.
+                pub(crate) fn resolve(self, os_version: Result<OsVersion, UserCallError>, direct_syscalls: bool) -> Resolution {
+                    let name = match self {
+                        $(Self::$name => concat!(stringify!($name), "\u{0}")),+
                     };
 
-                    // SAFETY: GetProcAddress returns a valid function pointer if the function exists.
-                    ptr = match unsafe { GetProcAddress(library, PCSTR(concat!(stringify!($name), "\u{0}").as_ptr()))  } {
-                        // SAFETY: All syscall signatures are set in stone and will not change.
-                        Some(f) => f as _,
-                        None => {
-                            nt_user_call_alternate!($name =>  $($($(#[$cfg])? $os = $syscall_nr),+)? => $rettype => $($paramname: $paramtype),*)
-                        }
+                    // SAFETY: `name` is null-terminated and contains no other null bytes.
+                    let name = unsafe { CStr::from_bytes_with_nul_unchecked(name.as_bytes()) };
+
+                    let export = match find_export(Library::Win32u, name) {
+                        Ok(function) => return resolve_stub(function, name, direct_syscalls),
+                        Err(UserCallError::LibraryNotFound { .. }) => find_export(Library::User32, name),
+                        export => export,
                     };
 
-                    ptr = FUNCTION.compare_exchange(std::ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire).map_or_else(|p| p, |_| ptr);
+                    match export {
+                        // All syscall signatures are set in stone and will not change.
+                        Ok(function) => Resolution::Function(function),
+                        Err(error @ UserCallError::LibraryNotFound { .. }) => Resolution::Error(error),
+                        Err(_) => match self {
+                            $(Self::$name => nt_user_call_alternate!($name os_version => $($($(#[$cfg])? $os = $syscall_nr),+)? => $rettype => $($paramname: $paramtype),*)),+
+                        },
+                    }
                 }
+            }
+
+            $(
+                #[cfg(feature = "unsafe-direct")]
+                #[allow(clippy::missing_safety_doc)]
+                #[expect(non_snake_case)]
+                $vis unsafe fn $name($($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
+                    // The `apfnSimpleCall` index is the last parameter.
+                    #[cfg(feature = "instrumentation")]
+                    let [.., index] = [$($paramname as u32),*];
+
+                    instrumented!(Entry { syscall: stringify!($name), index }, |dispatch| {
+                        let resolution = crate::resolution::resolved_family(Family::$name);
 
-                if (ptr as usize) < u16::MAX as usize {
-                    println!("{:?}", ptr as usize);
-                    return Err(UserCallError::try_from(ptr as usize).unwrap());
+                        if let Resolution::Error(error) = resolution {
+                            return Err(error);
+                        }
+
+                        dispatched!(dispatch = UserCall { index });
+                        nt_user_call_syscall_body!(resolution => ($($paramname: $paramtype),*) -> $rettype)
+                    })
                 }
+            )+
 
-                // SAFETY: The function pointer has been validated and matches the syscall signature.
-                let function: Function = unsafe {
-                    std::mem::transmute(ptr)
-                };
+            /// The syscalls invoked as resolved alongside the index of a function, without looking up their resolution.
+            pub(crate) mod dispatched {
+                use super::*;
 
-                // SAFETY: `function` is a valid function.
-                Ok(unsafe { function($($paramname),*) })
+                $(
+                    #[expect(non_snake_case)]
+                    pub(crate) unsafe fn $name(family: FamilyDispatch, $($paramname: $paramtype),*) -> Result<$rettype, UserCallError> {
+                        nt_user_call_syscall_body!(Resolution::from(family) => ($($paramname: $paramtype),*) -> $rettype)
+                    }
+                )+
             }
         };
     }
 
-/// Direct access to the underlying NtUserCall* syscalls.
+/// Resolves the operating system version and all functions up front, so that no call pays for the resolution.
 ///
-/// <div class="warning">Those syscalls were removed in Windows 11. This module does not provide a reverse mapping to the dedicated syscalls.</div>
-pub mod user_call {
-    use super::{
-        c_void, get_os_version, w, AtomicPtr, GetModuleHandleW, GetProcAddress, Ordering,
-        OsVersion, UserCallError, PCSTR,
+/// Fails with the error of the operating system version detection unless dedicated syscalls are used, or with
+/// [`UserCallError::OsNotSupported`] if the operating system does not match the version pinned at compile time.
+/// Functions that cannot be invoked on the current operating system do not fail the call.
+pub fn prewarm() -> Result<(), UserCallError> {
+    let os_version = match PINNED_OS_VERSION {
+        Some(pinned) if detect_os_version()? != pinned => {
+            return Err(UserCallError::OsNotSupported {
+                os_build: os_build(),
+            })
+        }
+        Some(pinned) => Ok(pinned),
+        None => get_os_version(),
     };
 
-    nt_user_call_syscall!(pub fn NtUserCallNoParam(call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4101, Win8 = 4102, Win81 = 4103);
-    nt_user_call_syscall!(pub fn NtUserCallOneParam(param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4098, Win8 = 4099, Win81 = 4100);
-    nt_user_call_syscall!(pub fn NtUserCallHwnd(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4364, Win8 = 4364, Win81 = 4365);
-    nt_user_call_syscall!(pub fn NtUserCallHwndSafe(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4364, Win8 = 4364, Win81 = 4365);
-    nt_user_call_syscall!(pub fn NtUserCallHwndOpt(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4743, Win8 = 4836, Win81 = 4869);
-    nt_user_call_syscall!(pub fn NtUserCallHwndParam(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4254, Win8 = 4254, Win81 = 4255);
-    nt_user_call_syscall!(pub fn NtUserCallHwndLock(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4129, Win8 = 4130, Win81 = 4131);
-    nt_user_call_syscall!(pub fn NtUserCallHwndParamLock(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4135, Win8 = 4136, Win81 = 4137);
-    nt_user_call_syscall!(pub fn NtUserCallHwndParamLockSafe(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4135, Win8 = 4136, Win81 = 4137);
-    nt_user_call_syscall!(pub fn NtUserCallTwoParam(param1: usize, param2: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4138, Win8 = 4138, Win81 = 4139);
+    for call in NtUserCall::ALL {
+        resolved(call);
+    }
+
+    if !has_dedicated_syscalls() {
+        for family in Family::ALL {
+            resolved_family(family);
+        }
+    }
+
+    match os_version {
+        Ok(_) | Err(UserCallError::OsTooNew { .. }) => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Invokes the syscall `number` with `args`, as decoded from a syscall stub by [`crate::resolution::resolve_stub`].
+#[cfg(target_arch = "x86_64")]
+pub(crate) unsafe fn direct_syscall<const N: usize>(number: u16, args: [usize; N]) -> usize {
+    use core::arch::asm;
+
+    let mut registers = [0; 3];
+    registers[..N].copy_from_slice(&args);
+    let result;
+
+    asm!(
+        "syscall",
+        inlateout("rax") number as usize => result,
+        in("r10") registers[0],
+        in("rdx") registers[1],
+        in("r8") registers[2],
+        clobber_abi("system"),
+        options(nostack),
+    );
+
+    result
 }
+
+/// Invokes the syscall `number` with `args`, as decoded from a syscall stub by [`crate::resolution::resolve_stub`].
+///
+/// Mirrors the stubs in [`nt_user_call_syscall_fn`], so only WOW64 processes are supported.
+#[cfg(target_arch = "x86")]
+pub(crate) unsafe fn direct_syscall<const N: usize>(number: u16, args: [usize; N]) -> usize {
+    use core::arch::asm;
+
+    let result;
+
+    asm!(
+        "mov ecx, {count}",
+        "jecxz 3f",
+        "2:",
+        "push dword ptr [{args} + ecx * 4 - 4]",
+        "loop 2b",
+        "3:",
+        "call 4f",
+        "jmp 5f",
+        "4:",
+        "mov eax, {number}",
+        "call dword ptr fs:[0xC0]",
+        "ret {size}",
+        "5:",
+        args = in(reg) args.as_ptr(),
+        number = in(reg) number as usize,
+        count = const(N),
+        size = const(N * 4),
+        lateout("eax") result,
+        out("ecx") _,
+        out("edx") _,
+    );
+
+    result
+}
+
+/// Syscall numbers are never decoded on ARM64, see [`crate::resolution::resolve_stub`].
+#[cfg(target_arch = "aarch64")]
+pub(crate) unsafe fn direct_syscall<const N: usize>(_number: u16, _args: [usize; N]) -> usize {
+    unreachable!("syscall numbers are only decoded on x86 and x64")
+}
+
+#[cfg(feature = "unsafe-direct")]
+pub mod user_call;
+#[cfg(not(feature = "unsafe-direct"))]
+#[cfg_attr(feature = "win11-only", allow(dead_code))]
+mod user_call;
+
+pub(crate) use user_call::Family;
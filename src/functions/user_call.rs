@@ -0,0 +1,31 @@
+//! Direct access to the underlying NtUserCall* syscalls.
+//!
+//! <div class="warning">Those syscalls were removed in Windows 11. This module does not provide a reverse mapping to the dedicated syscalls.</div>
+//!
+//! The functions take raw `apfnSimpleCall` indices and bypass the index tables in [`crate::indices`], so they are
+//! only public with the `unsafe-direct` feature enabled.
+//!
+//! On Windows 7 to 8.1, the syscalls are not exported and are invoked directly. In 32-bit processes, this is only
+//! supported under WOW64, which uses the syscall numbers of the x64 kernel; native 32-bit kernels, whose syscall
+//! numbers differ, fail with [`UserCallError::ArchitectureNotSupported`]. ARM64 processes fail with
+//! [`UserCallError::CallNotFound`] on those versions, which never ran on ARM64.
+
+use super::{
+    direct_syscall, find_export, resolve_stub, CStr, FamilyDispatch, Library, OsVersion,
+    Resolution, UserCallError,
+};
+#[cfg(target_arch = "x86")]
+use crate::version::is_wow64;
+
+nt_user_call_syscalls! {
+    pub fn NtUserCallNoParam(call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4101, Win8 = 4102, Win81 = 4103;
+    pub fn NtUserCallOneParam(param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4098, Win8 = 4099, Win81 = 4100;
+    pub fn NtUserCallHwnd(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4364, Win8 = 4364, Win81 = 4365;
+    pub fn NtUserCallHwndSafe(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4364, Win8 = 4364, Win81 = 4365;
+    pub fn NtUserCallHwndOpt(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4743, Win8 = 4836, Win81 = 4869;
+    pub fn NtUserCallHwndParam(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4254, Win8 = 4254, Win81 = 4255;
+    pub fn NtUserCallHwndLock(hwnd: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4129, Win8 = 4130, Win81 = 4131;
+    pub fn NtUserCallHwndParamLock(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4135, Win8 = 4136, Win81 = 4137;
+    pub fn NtUserCallHwndParamLockSafe(hwnd: usize, param: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4135, Win8 = 4136, Win81 = 4137;
+    pub fn NtUserCallTwoParam(param1: usize, param2: usize, call: u32) -> usize => #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))] Win7 = 4138, Win8 = 4138, Win81 = 4139;
+}
@@ -1,9 +1,38 @@
 //! Provides enumerations for the OS-specific `apfnSimpleCall` indices and a mapping from [`crate::functions::NtUserCall`] to the corresponding index.
+//!
+//! The tables cover the catalogued builds, with [`win10`] covering Windows 10 version 1903 and newer. Functions have no
+//! built-in index on earlier Windows 10 releases, whose tables differ and have not been catalogued, and fail with
+//! [`UserCallError::CallNotFound`] there. Tables registered via [`register_table`] take precedence over all of them,
+//! which allows supplying the indices of those releases. Indices discovered via [`discover`] are only used once
+//! registered via [`DiscoveredIndices::register`].
+//!
+//! With the `std` feature enabled, registered tables and discovered indices are owned by a
+//! [`crate::context::Context`], and the free functions of this module use [`crate::context::Context::global`].
 
-use crate::version::{get_os_version, OsVersion};
+use crate::{
+    error::UserCallError,
+    version::{OsVersion, Win10Build, PINNED_OS_VERSION},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::version::get_os_version;
 
 use super::functions::NtUserCall;
 
+#[cfg(feature = "std")]
+mod discovery;
+#[cfg(feature = "std")]
+mod overrides;
+
+#[cfg(feature = "std")]
+pub(crate) use discovery::discover_indices;
+#[cfg(feature = "std")]
+pub use discovery::{discover, DiscoveredIndices};
+#[cfg(feature = "std")]
+pub(crate) use overrides::registered_index;
+#[cfg(feature = "std")]
+pub use overrides::{clear_tables, register_table, IndexTable};
+
 macro_rules! user_call_enum {
     ( $os:ident { $($variant:ident $(= $index:literal)?),+ } ) => {
         pub mod $os {
@@ -26,6 +55,15 @@ macro_rules! user_call_enum {
                     }
                 }
             }
+
+            /// Returns the index of `call`, if it exists on this operating system.
+            pub const fn index(call: super::NtUserCall) -> Option<u16> {
+                match call {
+                    $(super::NtUserCall::$variant => Some(NtUserCallIndex::$variant as u16),)+
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
         }
     };
 }
@@ -569,13 +607,71 @@ user_call_enum! {
     }
 }
 
+/// Returns the index of `call` on the operating system pinned via the `pin-*` features, at compile time.
+///
+/// Registered tables and discovered indices are not consulted, as pinned indices are resolved at compile time.
+pub(crate) const fn pinned_index(call: NtUserCall) -> Option<u16> {
+    match PINNED_OS_VERSION {
+        #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))]
+        Some(OsVersion::Win7) => win7::index(call),
+        Some(OsVersion::Win8) => win8::index(call),
+        Some(OsVersion::Win81) => win81::index(call),
+        Some(OsVersion::Win10(Win10Build::V1903)) => win10::index(call),
+        Some(OsVersion::Win10(_)) | None => None,
+    }
+}
+
+#[cfg(feature = "std")]
 pub fn get_index(call: NtUserCall) -> Option<u16> {
-    match get_os_version() {
+    crate::context::Context::global().get_index(call)
+}
+
+#[cfg(not(feature = "std"))]
+pub fn get_index(call: NtUserCall) -> Option<u16> {
+    get_index_for(get_os_version(), call)
+}
+
+/// Returns the index of `call` from the built-in table of `os_version`, without consulting registered tables.
+pub(crate) fn get_index_for(
+    os_version: Result<OsVersion, UserCallError>,
+    call: NtUserCall,
+) -> Option<u16> {
+    match os_version {
         #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))]
         Ok(OsVersion::Win7) => win7::NtUserCallIndex::try_from(call).ok().map(|c| c as _),
         Ok(OsVersion::Win8) => win8::NtUserCallIndex::try_from(call).ok().map(|c| c as _),
         Ok(OsVersion::Win81) => win81::NtUserCallIndex::try_from(call).ok().map(|c| c as _),
-        Ok(OsVersion::Win10) => win10::NtUserCallIndex::try_from(call).ok().map(|c| c as _),
-        Err(_) => None,
+        Ok(OsVersion::Win10(Win10Build::V1903)) => {
+            win10::NtUserCallIndex::try_from(call).ok().map(|c| c as _)
+        }
+        // Entries have been added, removed and renumbered before version 1903, whose tables have not been catalogued.
+        // Calling them with the indices of version 1903 would invoke mismatched functions.
+        Ok(OsVersion::Win10(
+            Win10Build::V1507
+            | Win10Build::V1511
+            | Win10Build::V1607
+            | Win10Build::V1703
+            | Win10Build::V1709
+            | Win10Build::V1803
+            | Win10Build::V1809,
+        ))
+        | Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{functions::NtUserCall, indices::win10};
+
+    #[test]
+    pub fn index_should_match_try_from() {
+        for call in NtUserCall::ALL {
+            assert_eq!(
+                win10::index(call),
+                win10::NtUserCallIndex::try_from(call)
+                    .ok()
+                    .map(|index| index as u16)
+            );
+        }
     }
 }
@@ -0,0 +1,364 @@
+//! Discovers `apfnSimpleCall` indices at runtime from the code of user32.dll.
+//!
+//! Many functions are exported from user32.dll under their own name and consist of little more than loading their
+//! index into a parameter register and calling the `NtUserCall*` syscall through the import address table. Decoding
+//! these exports yields the indices of the running build, without relying on the tables in [`crate::indices`].
+//!
+//! Discovery is only implemented for x64 and fails with [`UserCallError::ArchitectureNotSupported`] elsewhere. It is
+//! best-effort and conservative: functions not exported from user32.dll, exported with code deviating from the pattern
+//! above in any instruction, or whose index has been discovered for another function as well are not discovered.
+//!
+//! Discovered indices are never used to invoke functions on their own, as a misdecoded index would invoke a different
+//! function. They are only used once registered explicitly via [`DiscoveredIndices::register`].
+
+use crate::{
+    context::Context, error::UserCallError, functions::NtUserCall, indices::IndexTable,
+    version::os_build,
+};
+
+/// The indices discovered by [`discover`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiscoveredIndices {
+    indices: [Option<u16>; NtUserCall::COUNT],
+}
+
+impl DiscoveredIndices {
+    /// Returns the discovered index of `call`.
+    pub fn get(&self, call: NtUserCall) -> Option<u16> {
+        self.indices[call as usize]
+    }
+
+    /// Returns an iterator over all functions whose index has been discovered.
+    pub fn iter(&self) -> impl Iterator<Item = (NtUserCall, u16)> + '_ {
+        NtUserCall::ALL
+            .into_iter()
+            .filter_map(|call| Some((call, self.get(call)?)))
+    }
+
+    /// Returns the number of discovered indices.
+    pub fn len(&self) -> usize {
+        self.indices.iter().flatten().count()
+    }
+
+    /// Returns whether no indices have been discovered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the discovered indices as a table for the running build.
+    pub fn to_table(&self) -> IndexTable {
+        let build = os_build().build;
+
+        IndexTable {
+            builds: build..=build,
+            indices: self.iter().collect(),
+        }
+    }
+
+    /// Registers the discovered indices for the running build, so that [`crate::indices::get_index`] and the functions
+    /// in [`crate::functions`] use them. See [`crate::indices::register_table`].
+    pub fn register(&self) {
+        self.to_table().register();
+    }
+}
+
+/// Discovers the `apfnSimpleCall` indices of the running build from user32.dll, which must be loaded.
+///
+/// The discovered indices are not used by [`crate::indices::get_index`] and the functions in [`crate::functions`]
+/// unless registered via [`DiscoveredIndices::register`]. Discovery is only performed once; subsequent calls return
+/// the same indices. Discovery uses the global context; see [`Context::discover`].
+pub fn discover() -> Result<&'static DiscoveredIndices, UserCallError> {
+    Context::global().discover()
+}
+
+/// Discovers the `apfnSimpleCall` indices of the running build, resolving the `NtUserCall*` syscalls for the operating
+/// system version of `context`.
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn discover_indices(context: &Context) -> Result<DiscoveredIndices, UserCallError> {
+    use windows::{
+        core::{w, PCSTR},
+        Win32::{
+            Foundation::GetLastError,
+            System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+        },
+    };
+
+    use crate::{
+        pe::Image,
+        resolution::{Library, Resolution},
+    };
+
+    // SAFETY: `w!` returns a valid null-terminated string.
+    let user32 = unsafe { GetModuleHandleW(w!("user32")) }.map_err(|_| {
+        UserCallError::LibraryNotFound {
+            library: Library::User32.name(),
+            // SAFETY: `GetLastError` has no preconditions.
+            last_error: Some(unsafe { GetLastError() }.0),
+        }
+    })?;
+    let base = user32.0 as *const u8;
+
+    // SAFETY: user32.dll is never unloaded once loaded.
+    let image =
+        unsafe { Image::from_module(base) }.map_err(|_| UserCallError::LibraryNotFound {
+            library: Library::User32.name(),
+            last_error: None,
+        })?;
+    let os_version = context.os_version();
+
+    let mut indices = [None; NtUserCall::COUNT];
+
+    for call in NtUserCall::ALL {
+        let family = call.family();
+
+        let Resolution::Function(family_function) = family.resolve(os_version, false) else {
+            continue;
+        };
+
+        let name = format!("{}\0", call.name());
+
+        // SAFETY: `name` is a valid null-terminated string.
+        let Some(export) = (unsafe { GetProcAddress(user32, PCSTR(name.as_ptr())) }) else {
+            continue;
+        };
+
+        let Some(rva) = (export as usize).checked_sub(base as usize) else {
+            continue;
+        };
+
+        let Ok(code) = image.bytes(rva as u32, 64) else {
+            continue;
+        };
+
+        indices[call as usize] = decode_index(code, family.index_parameter(), |slot| {
+            image
+                .bytes((rva as isize + slot) as u32, 8)
+                .ok()
+                .and_then(|slot| <[u8; 8]>::try_from(slot).ok())
+                .is_some_and(|slot| usize::from_le_bytes(slot) == family_function as usize)
+        });
+    }
+
+    remove_ambiguous(&mut indices);
+
+    Ok(DiscoveredIndices { indices })
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn discover_indices(_context: &Context) -> Result<DiscoveredIndices, UserCallError> {
+    Err(UserCallError::ArchitectureNotSupported)
+}
+
+/// The number of the x64 registers the first three parameters are passed in, `rcx`, `rdx` and `r8`.
+#[cfg(target_arch = "x86_64")]
+const PARAMETER_REGISTERS: [u8; 3] = [1, 2, 8];
+
+/// The exclusive upper bound of `apfnSimpleCall` indices, which has never had more than 256 entries.
+#[cfg(target_arch = "x86_64")]
+const MAX_INDEX: u32 = 0x100;
+
+/// An x64 instruction accepted by [`decode_index`].
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Instruction {
+    /// `sub rsp, imm8`.
+    AllocateStack,
+    /// `mov r32, imm32` or `xor r32, r32`, loading `value` into the register numbered `register`.
+    LoadImmediate { register: u8, value: u32 },
+    /// `mov` from a register into the register numbered `register`.
+    MoveRegister { register: u8 },
+    /// `call [rip + disp32]` or `jmp [rip + disp32]`, with the displacement relative to the end of the instruction.
+    IndirectBranch { displacement: i32 },
+}
+
+/// Decodes the instruction at the start of `code`, returning it with its length, if it is one of [`Instruction`].
+#[cfg(target_arch = "x86_64")]
+fn decode_instruction(code: &[u8]) -> Option<(Instruction, usize)> {
+    let (rex, rest) = match code {
+        [rex @ 0x40..=0x4F, rest @ ..] => (*rex, rest),
+        _ => (0, code),
+    };
+
+    let prefix = usize::from(rex != 0);
+    let wide = rex & 0b1000 != 0;
+    let extend_reg = (rex & 0b0100) << 1;
+    let extend_rm = (rex & 0b0001) << 3;
+
+    let (instruction, length) = match *rest {
+        [0x83, 0xEC, _, ..] if rex == 0x48 => (Instruction::AllocateStack, 3),
+        [opcode @ 0xB8..=0xBF, a, b, c, d, ..] if !wide => (
+            Instruction::LoadImmediate {
+                register: (opcode - 0xB8) | extend_rm,
+                value: u32::from_le_bytes([a, b, c, d]),
+            },
+            5,
+        ),
+        [0x31 | 0x33, modrm, ..]
+            if !wide
+                && modrm >> 6 == 0b11
+                && (modrm >> 3 & 7) | extend_reg == (modrm & 7) | extend_rm =>
+        {
+            (
+                Instruction::LoadImmediate {
+                    register: (modrm & 7) | extend_rm,
+                    value: 0,
+                },
+                2,
+            )
+        }
+        [0x89, modrm, ..] if modrm >> 6 == 0b11 => (
+            Instruction::MoveRegister {
+                register: (modrm & 7) | extend_rm,
+            },
+            2,
+        ),
+        [0x8B, modrm, ..] if modrm >> 6 == 0b11 => (
+            Instruction::MoveRegister {
+                register: (modrm >> 3 & 7) | extend_reg,
+            },
+            2,
+        ),
+        [0xFF, 0x15 | 0x25, a, b, c, d, ..] if rex == 0 || rex == 0x48 => (
+            Instruction::IndirectBranch {
+                displacement: i32::from_le_bytes([a, b, c, d]),
+            },
+            6,
+        ),
+        _ => return None,
+    };
+
+    Some((instruction, prefix + length))
+}
+
+/// Decodes the index the x64 code in `code` passes in parameter `parameter` to the `NtUserCall*` syscall.
+///
+/// The code must consist of exactly, in this order, an optional `sub rsp, imm8`, a single `mov r32, imm32` or
+/// `xor r32, r32` loading the index into the register of `parameter`, interleaved with `mov` instructions between other
+/// registers, and a `call` or `jmp` through the slot for which `is_family_slot` returns true. `is_family_slot` receives
+/// the offset of the slot relative to the start of `code`. Any other instruction, a second load of the index, a branch
+/// through another slot or an index beyond the size of `apfnSimpleCall` rejects the code.
+#[cfg(target_arch = "x86_64")]
+fn decode_index(
+    code: &[u8],
+    parameter: usize,
+    is_family_slot: impl Fn(isize) -> bool,
+) -> Option<u16> {
+    let index_register = *PARAMETER_REGISTERS.get(parameter)?;
+    let mut index = None;
+    let mut offset = 0;
+
+    loop {
+        let (instruction, length) = decode_instruction(code.get(offset..)?)?;
+        let start = offset;
+        offset += length;
+
+        match instruction {
+            Instruction::AllocateStack if start == 0 => {}
+            Instruction::LoadImmediate { register, value }
+                if register == index_register && index.is_none() =>
+            {
+                index = Some(value);
+            }
+            Instruction::MoveRegister { register } if register != index_register => {}
+            Instruction::IndirectBranch { displacement } => {
+                if !is_family_slot(offset as isize + displacement as isize) {
+                    return None;
+                }
+
+                return index
+                    .filter(|&index| index < MAX_INDEX)
+                    .map(|index| index as u16);
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Removes indices discovered for more than one function, as every `apfnSimpleCall` entry belongs to one function.
+#[cfg(target_arch = "x86_64")]
+fn remove_ambiguous(indices: &mut [Option<u16>; NtUserCall::COUNT]) {
+    let discovered = *indices;
+
+    for index in indices.iter_mut() {
+        if index.is_some_and(|index| {
+            discovered
+                .iter()
+                .filter(|&&other| other == Some(index))
+                .count()
+                > 1
+        }) {
+            *index = None;
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod test {
+    use crate::{
+        functions::NtUserCall,
+        indices::discovery::{decode_index, remove_ambiguous},
+    };
+
+    #[test]
+    pub fn decode_index_should_accept_index_load_before_family_branch() {
+        // mov edx, 0x2A; jmp [rip + 0x100]
+        let code = [
+            0xBA, 0x2A, 0x00, 0x00, 0x00, 0xFF, 0x25, 0x00, 0x01, 0x00, 0x00,
+        ];
+
+        assert_eq!(decode_index(&code, 1, |slot| slot == 0x10B), Some(0x2A));
+        assert_eq!(decode_index(&code, 0, |slot| slot == 0x10B), None);
+        assert_eq!(decode_index(&code, 1, |_| false), None);
+
+        // sub rsp, 0x28; mov r8d, edx; xor ecx, ecx; call [rip + 0x100]; add rsp, 0x28; ret
+        let code = [
+            0x48, 0x83, 0xEC, 0x28, 0x44, 0x8B, 0xC2, 0x33, 0xC9, 0xFF, 0x15, 0x00, 0x01, 0x00,
+            0x00, 0x48, 0x83, 0xC4, 0x28, 0xC3,
+        ];
+
+        assert_eq!(decode_index(&code, 0, |slot| slot == 0x10F), Some(0));
+    }
+
+    #[test]
+    pub fn decode_index_should_reject_ambiguous_code() {
+        let slot = |slot| slot == 0x110;
+
+        // mov edx, 0x2A; mov edx, 0x2B; jmp [rip + 0x100]
+        let reloaded = [
+            0xBA, 0x2A, 0x00, 0x00, 0x00, 0xBA, 0x2B, 0x00, 0x00, 0x00, 0xFF, 0x25, 0x00, 0x01,
+            0x00, 0x00,
+        ];
+        // mov edx, 0x2A; mov edx, ecx; nop; jmp [rip + 0x100]
+        let overwritten = [
+            0xBA, 0x2A, 0x00, 0x00, 0x00, 0x8B, 0xD1, 0x90, 0xFF, 0x25, 0x00, 0x01, 0x00, 0x00,
+        ];
+        // push rbx; mov edx, 0x2A; mov ecx, 0x2A; jmp [rip + 0x100]
+        let unknown = [
+            0x53, 0xBA, 0x2A, 0x00, 0x00, 0x00, 0xB9, 0x2A, 0x00, 0x00, 0x00, 0xFF, 0x25, 0x00,
+            0x01, 0x00,
+        ];
+        // mov edx, 0x1000; jmp [rip + 0x100]
+        let out_of_range = [
+            0xBA, 0x00, 0x10, 0x00, 0x00, 0xFF, 0x25, 0x00, 0x01, 0x00, 0x00,
+        ];
+
+        assert_eq!(decode_index(&reloaded, 1, slot), None);
+        assert_eq!(decode_index(&overwritten, 1, slot), None);
+        assert_eq!(decode_index(&unknown, 1, slot), None);
+        assert_eq!(decode_index(&out_of_range, 1, |slot| slot == 0x10B), None);
+    }
+
+    #[test]
+    pub fn remove_ambiguous_should_drop_shared_indices() {
+        let mut indices = [None; NtUserCall::COUNT];
+        indices[NtUserCall::CreateMenu as usize] = Some(0);
+        indices[NtUserCall::SetCaretPos as usize] = Some(7);
+        indices[NtUserCall::DestroyCaret as usize] = Some(7);
+
+        remove_ambiguous(&mut indices);
+
+        assert_eq!(indices[NtUserCall::CreateMenu as usize], Some(0));
+        assert_eq!(indices[NtUserCall::SetCaretPos as usize], None);
+        assert_eq!(indices[NtUserCall::DestroyCaret as usize], None);
+    }
+}
@@ -0,0 +1,85 @@
+//! Provides registering `apfnSimpleCall` indices at runtime, for builds the tables in [`crate::indices`] do not cover.
+
+use std::ops::RangeInclusive;
+
+use crate::{context::Context, functions::NtUserCall, version::os_build};
+
+/// The `apfnSimpleCall` indices of a range of operating system builds, as registered via [`register_table`].
+///
+/// With the `serde` feature enabled, tables can be deserialized from any format, e.g. to ship them as configuration
+/// files, and registered via [`IndexTable::register`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexTable {
+    /// The build numbers the indices apply to, as reported by [`crate::version::os_build`].
+    pub builds: RangeInclusive<u32>,
+    pub indices: Vec<(NtUserCall, u16)>,
+}
+
+impl IndexTable {
+    /// Registers this table with the global context. See [`register_table`].
+    pub fn register(self) {
+        Context::global().register_table(self);
+    }
+
+    /// Returns the index of `call` in this table.
+    pub fn get(&self, call: NtUserCall) -> Option<u16> {
+        self.indices
+            .iter()
+            .find_map(|&(entry, index)| (entry == call).then_some(index))
+    }
+}
+
+/// Registers `indices` for the operating system builds in `builds` with the global context.
+///
+/// [`crate::indices::get_index`] and the functions in [`crate::functions`] consult registered tables matching the
+/// running build before the built-in tables, with later registrations taking precedence. This allows supplying indices
+/// for builds whose version is not supported or whose indices differ from the built-in tables. Functions already called
+/// before registration keep their cached resolution, so tables should be registered early.
+pub fn register_table(builds: RangeInclusive<u32>, indices: &[(NtUserCall, u16)]) {
+    IndexTable {
+        builds,
+        indices: indices.to_vec(),
+    }
+    .register();
+}
+
+/// Removes all tables registered with the global context.
+pub fn clear_tables() {
+    Context::global().clear_tables();
+}
+
+/// Returns the index of `call` from the most recently registered of `tables` matching the running build.
+pub(crate) fn registered_index(tables: &[IndexTable], call: NtUserCall) -> Option<u16> {
+    if tables.is_empty() {
+        return None;
+    }
+
+    let build = os_build().build;
+
+    tables
+        .iter()
+        .rev()
+        .filter(|table| table.builds.contains(&build))
+        .find_map(|table| table.get(call))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{functions::NtUserCall, indices::overrides::IndexTable};
+
+    #[test]
+    pub fn index_table_should_return_first_matching_entry() {
+        let table = IndexTable {
+            builds: 26100..=26100,
+            indices: vec![
+                (NtUserCall::CreateMenu, 0),
+                (NtUserCall::SetCaretPos, 42),
+                (NtUserCall::SetCaretPos, 43),
+            ],
+        };
+
+        assert_eq!(table.get(NtUserCall::SetCaretPos), Some(42));
+        assert_eq!(table.get(NtUserCall::DestroyCaret), None);
+    }
+}
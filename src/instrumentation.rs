@@ -0,0 +1,203 @@
+//! Provides optional instrumentation of the functions in [`crate::functions`].
+//!
+//! Calls taking longer than a configurable threshold are reported to a handler as a [`SlowCall`]. This helps spotting
+//! calls that block on the kernel-side user critical section under load.
+//!
+//! Every call can be reported to an observer set via [`set_call_observer`] as an [`ObservedCall`], e.g. to log all
+//! calls made by the process. This covers the functions in [`crate::functions`], [`crate::raw`] and
+//! [`crate::context::Context`], including calls dispatched to a backend and calls failing before any syscall is
+//! invoked.
+//!
+//! With the `tracing` feature enabled, function resolution and every call are additionally emitted as `tracing` events
+//! at the `DEBUG` and `TRACE` level, respectively, and slow calls at the `WARN` level.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::{error::UserCallError, functions::NtUserCall};
+
+/// What has been called.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Callee {
+    /// A function in [`crate::functions`], also when invoked via [`crate::raw::call`] or a [`crate::context::Context`].
+    Function(NtUserCall),
+    /// The `apfnSimpleCall` entry `index`, invoked by raw index via the `NtUserCall*` syscall `syscall`.
+    Entry { syscall: &'static str, index: u32 },
+}
+
+impl Display for Callee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Function(call) => write!(f, "NtUser{}", call.name()),
+            Self::Entry { syscall, index } => write!(f, "{syscall} entry {index}"),
+        }
+    }
+}
+
+/// How a function has been dispatched.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Dispatch {
+    /// The function has been invoked via its dedicated syscall exported from win32u.dll.
+    DedicatedSyscall,
+    /// The function has been invoked via the `NtUserCall*` family of syscalls with the given index.
+    UserCall { index: u32 },
+    /// The function has been invoked directly via the syscall with the given number, bypassing its export.
+    DirectSyscall { number: u16 },
+    /// The call has been dispatched to a backend installed via `crate::backend`.
+    Backend,
+    /// The function has not been invoked, as it could not be resolved or its arguments were invalid.
+    Unresolved,
+}
+
+impl Display for Dispatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DedicatedSyscall => write!(f, "dedicated syscall"),
+            Self::UserCall { index } => write!(f, "NtUserCall* index {index}"),
+            Self::DirectSyscall { number } => write!(f, "direct syscall {number:#x}"),
+            Self::Backend => write!(f, "backend"),
+            Self::Unresolved => write!(f, "no dispatch"),
+        }
+    }
+}
+
+/// A call that exceeded the threshold set via [`set_slow_call_threshold`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SlowCall {
+    pub call: Callee,
+    pub dispatch: Dispatch,
+    pub elapsed: Duration,
+}
+
+impl Display for SlowCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} took {} µs via {}",
+            self.call,
+            self.elapsed.as_micros(),
+            self.dispatch
+        )
+    }
+}
+
+/// A call reported to the observer set via [`set_call_observer`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ObservedCall {
+    pub call: Callee,
+    pub dispatch: Dispatch,
+    /// The raw return value of the call, or the error it failed with.
+    pub result: Result<usize, UserCallError>,
+}
+
+impl Display for ObservedCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} via {} ", self.call, self.dispatch)?;
+
+        match self.result {
+            Ok(value) => write!(f, "returned {value:#x}"),
+            Err(error) => write!(f, "failed: {error}"),
+        }
+    }
+}
+
+/// Converts the return value of a call into the raw value reported in an [`ObservedCall`].
+pub(crate) trait CallOutcome {
+    fn call_outcome(&self) -> Result<usize, UserCallError>;
+}
+
+const DISABLED: u64 = u64::MAX;
+
+static SLOW_CALL_THRESHOLD: AtomicU64 = AtomicU64::new(DISABLED);
+
+static SLOW_CALL_HANDLER: RwLock<Option<fn(&SlowCall)>> = RwLock::new(None);
+
+/// Sets the duration above which calls are reported as slow. `None` disables slow call detection, which is the default.
+pub fn set_slow_call_threshold(threshold: Option<Duration>) {
+    let threshold = threshold.map_or(DISABLED, |threshold| {
+        threshold.as_micros().min((DISABLED - 1) as u128) as u64
+    });
+
+    SLOW_CALL_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+/// Returns the duration above which calls are reported as slow, if slow call detection is enabled.
+pub fn slow_call_threshold() -> Option<Duration> {
+    match SLOW_CALL_THRESHOLD.load(Ordering::Relaxed) {
+        DISABLED => None,
+        threshold => Some(Duration::from_micros(threshold)),
+    }
+}
+
+/// Sets the handler invoked on the calling thread for every slow call, replacing the previous handler.
+pub fn set_slow_call_handler(handler: Option<fn(&SlowCall)>) {
+    *SLOW_CALL_HANDLER
+        .write()
+        .unwrap_or_else(|err| err.into_inner()) = handler;
+}
+
+static OBSERVED: AtomicBool = AtomicBool::new(false);
+
+static CALL_OBSERVER: RwLock<Option<fn(&ObservedCall)>> = RwLock::new(None);
+
+/// Sets the observer invoked on the calling thread after every call, replacing the previous observer.
+///
+/// Calls made while the observer runs are reported to it as well.
+pub fn set_call_observer(observer: Option<fn(&ObservedCall)>) {
+    let mut call_observer = CALL_OBSERVER.write().unwrap_or_else(|err| err.into_inner());
+
+    *call_observer = observer;
+    OBSERVED.store(observer.is_some(), Ordering::Relaxed);
+}
+
+/// Invokes `function`, which records how the call is dispatched in its parameter, and reports the call. Calls that
+/// fail before being dispatched are reported with [`Dispatch::Unresolved`].
+pub(crate) fn instrument<T: CallOutcome>(
+    call: Callee,
+    function: impl FnOnce(&mut Dispatch) -> T,
+) -> T {
+    let threshold = SLOW_CALL_THRESHOLD.load(Ordering::Relaxed);
+    let start = (threshold != DISABLED).then(Instant::now);
+
+    let mut dispatch = Dispatch::Unresolved;
+    let result = function(&mut dispatch);
+    let elapsed = start.map(|start| start.elapsed());
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(%call, %dispatch, result = ?result.call_outcome(), "called function");
+
+    if OBSERVED.load(Ordering::Relaxed) {
+        let observer = *CALL_OBSERVER.read().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(observer) = observer {
+            observer(&ObservedCall {
+                call,
+                dispatch,
+                result: result.call_outcome(),
+            });
+        }
+    }
+
+    if let Some(elapsed) = elapsed.filter(|elapsed| elapsed.as_micros() > threshold as u128) {
+        let handler = *SLOW_CALL_HANDLER
+            .read()
+            .unwrap_or_else(|err| err.into_inner());
+
+        let slow_call = SlowCall {
+            call,
+            dispatch,
+            elapsed,
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%slow_call, "slow call");
+
+        if let Some(handler) = handler {
+            handler(&slow_call);
+        }
+    }
+
+    result
+}
@@ -10,11 +10,51 @@
 //!
 //! This library provides a unified interface to all of these functions by abstracting away of the differences between
 //! indices, syscall availability and exported syscalls in Windows 11.
+//!
+//! The default `std` feature provides the typed functions in [`functions`] and everything built on top of them. Without
+//! it, the crate is `no_std`, does not depend on the `windows` crate, and looks up exports and the operating system
+//! version via the PEB instead of importing any functions; functions are then invoked with raw values via [`raw`].
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(clippy::undocumented_unsafe_blocks)]
 
+#[cfg(feature = "std")]
+pub mod availability;
+#[cfg(feature = "backend")]
+pub mod backend;
+#[cfg(feature = "std")]
+pub mod capabilities;
+#[cfg(feature = "std")]
+pub mod context;
+#[cfg(feature = "std")]
+pub mod desktop;
+#[cfg(feature = "std")]
+pub mod device_change;
+#[cfg(feature = "devtools")]
+pub mod devtools;
 pub mod error;
 pub mod functions;
 pub mod indices;
+#[cfg(feature = "instrumentation")]
+pub mod instrumentation;
+#[cfg(feature = "std")]
 pub mod macros;
+#[cfg(feature = "std")]
+pub mod modern_app;
+#[cfg(all(
+    feature = "std",
+    any(feature = "devtools", target_arch = "x86_64", target_arch = "x86")
+))]
+mod pe;
+#[cfg(not(feature = "std"))]
+mod peb;
+pub mod raw;
+mod resolution;
+#[cfg(feature = "std")]
+pub mod safe;
+#[cfg(feature = "std")]
+pub mod session;
 pub mod version;
+
+#[cfg(feature = "std")]
+pub use capabilities::capabilities;
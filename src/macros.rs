@@ -3,43 +3,54 @@ macro_rules! load_runtime_fn_body {
     (
         [ $library:literal ] $name:ident ($($paramname:ident: $paramtype:ty),*) -> $rettype:ty
     ) => {{
-        use ::std::sync::atomic::{AtomicPtr, Ordering};
-        use ::windows::{core::{w, PCSTR}, Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress}};
+        use ::std::{ffi::CStr, sync::{atomic::{AtomicPtr, Ordering}, OnceLock}};
+        use ::windows::{core::{w, PCSTR}, Win32::{Foundation::GetLastError, System::LibraryLoader::{GetModuleHandleW, GetProcAddress}}};
         use $crate::error::UserCallError;
 
         type Function = unsafe extern "system" fn($($paramtype),*) -> $rettype;
         static FUNCTION: AtomicPtr<::std::ffi::c_void> = AtomicPtr::new(std::ptr::null_mut());
+        static ERROR: OnceLock<UserCallError> = OnceLock::new();
+
+        const EXPORT: &CStr = match CStr::from_bytes_with_nul(concat!("NtUser", stringify!($name), "\u{0}").as_bytes()) {
+            Ok(export) => export,
+            Err(_) => panic!("export names contain no null bytes"),
+        };
 
         let mut ptr = FUNCTION.load(Ordering::Relaxed);
 
         if ptr.is_null() {
+            if let Some(error) = ERROR.get() {
+                return Err(*error);
+            }
+
             // SAFETY: On success, GetModuleHandleW returns a valid module handle
             let win32u = match unsafe { GetModuleHandleW(w!($library)) } {
                 Ok(library) => library,
                 Err(_) => {
-                    _ = FUNCTION.compare_exchange(std::ptr::null_mut(), UserCallError::LibraryNotFound as usize as _, Ordering::AcqRel, Ordering::Acquire);
-                    return Err(UserCallError::LibraryNotFound);
+                    return Err(*ERROR.get_or_init(|| UserCallError::LibraryNotFound {
+                        library: $library,
+                        // SAFETY: `GetLastError` has no preconditions.
+                        last_error: Some(unsafe { GetLastError() }.0),
+                    }));
                 }
             };
 
             // SAFETY: GetProcAddress returns a valid function pointer if the function exists.
-            ptr = match unsafe { GetProcAddress(win32u, PCSTR(concat!("NtUser", stringify!($name), "\u{0}").as_ptr())) } {
+            ptr = match unsafe { GetProcAddress(win32u, PCSTR(EXPORT.as_ptr().cast())) } {
                 Some(f) => f,
                 None => {
-                    _ = FUNCTION.compare_exchange(std::ptr::null_mut(), UserCallError::CallNotFound as usize as _, Ordering::AcqRel, Ordering::Acquire);
-                    return Err(UserCallError::CallNotFound);
+                    return Err(*ERROR.get_or_init(|| UserCallError::CallNotFound {
+                        export: Some(EXPORT),
+                        // SAFETY: `GetLastError` has no preconditions.
+                        last_error: Some(unsafe { GetLastError() }.0),
+                    }));
                 }
             } as _;
-        }
 
-        else if (ptr as usize) < u16::MAX as usize {
-            // SAFETY: All possible error values have been written by the compare_exchange calls above and are variants of UserCallError
-            return Err(unsafe {
-                UserCallError::try_from(ptr as usize).unwrap_unchecked()
-            });
+            FUNCTION.store(ptr, Ordering::Relaxed);
         }
 
-        // SAFETY: All non-function values have been handled and the pointer is valid function pointer
+        // SAFETY: The pointer is a valid function pointer
         let function: Function = unsafe {
             std::mem::transmute(ptr)
         };
@@ -51,8 +62,6 @@ macro_rules! load_runtime_fn_body {
     }}
 }
 
-pub(crate) use load_runtime_fn_body;
-
 #[macro_export]
 macro_rules! load_runtime_fn {
     (
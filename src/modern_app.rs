@@ -0,0 +1,100 @@
+//! Provides discovery of UWP app windows, built on [`NtUserGetModernAppWindow`].
+//!
+//! UWP apps render into a `Windows.UI.Core.CoreWindow` owned by the app process. Unless the app runs in full screen,
+//! that window is hosted by an `ApplicationFrameWindow` owned by `ApplicationFrameHost.exe`, which is the window
+//! that shows up when enumerating top-level windows. While the app is minimized or suspended, the core window is
+//! detached from its frame.
+
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::HWND,
+        UI::WindowsAndMessaging::{
+            FindWindowExW, GetAncestor, GetClassNameW, GetWindowThreadProcessId, GA_PARENT,
+        },
+    },
+};
+
+use crate::functions::NtUserGetModernAppWindow;
+
+/// The window class of the frame window hosting UWP apps.
+pub const APPLICATION_FRAME_WINDOW_CLASS: &str = "ApplicationFrameWindow";
+
+/// The window class of the window UWP apps render into.
+pub const CORE_WINDOW_CLASS: &str = "Windows.UI.Core.CoreWindow";
+
+/// The windows making up a UWP app, as resolved by [`modern_app_window`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ModernAppWindow {
+    /// The `ApplicationFrameWindow` hosting the app, if the core window is currently hosted by one.
+    pub frame: Option<HWND>,
+    /// The `Windows.UI.Core.CoreWindow` of the app.
+    pub core_window: HWND,
+    /// The id of the app process owning the core window.
+    pub process_id: u32,
+}
+
+/// Resolves the UWP app windows associated with the top-level window `hwnd`.
+///
+/// `hwnd` may either be an `ApplicationFrameWindow`, a `Windows.UI.Core.CoreWindow` or any other window registered
+/// as a modern app window. Returns `None` if `hwnd` is not associated with a UWP app or the app's core window is
+/// currently detached from its frame.
+pub fn modern_app_window(hwnd: HWND) -> windows::core::Result<Option<ModernAppWindow>> {
+    let is_frame = has_class(hwnd, APPLICATION_FRAME_WINDOW_CLASS);
+
+    let (frame, core_window) = if has_class(hwnd, CORE_WINDOW_CLASS) {
+        // SAFETY: `GetAncestor` accepts any window handle and returns a null handle on failure.
+        let parent = unsafe { GetAncestor(hwnd, GA_PARENT) };
+
+        (
+            has_class(parent, APPLICATION_FRAME_WINDOW_CLASS).then_some(parent),
+            hwnd,
+        )
+    } else {
+        // SAFETY: `NtUserGetModernAppWindow` accepts any window handle and returns a null handle on failure.
+        let modern = unsafe { NtUserGetModernAppWindow(hwnd) }?;
+
+        if !modern.is_invalid() {
+            (is_frame.then_some(hwnd), modern)
+        } else if is_frame {
+            // SAFETY: `FindWindowExW` is called with a valid window class and returns an error if no child window
+            // matches.
+            let child =
+                unsafe { FindWindowExW(Some(hwnd), None, &HSTRING::from(CORE_WINDOW_CLASS), None) };
+
+            match child {
+                Ok(child) => (Some(hwnd), child),
+                Err(_) => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        }
+    };
+
+    let mut process_id = 0u32;
+
+    // SAFETY: `process_id` is valid for writes.
+    unsafe {
+        GetWindowThreadProcessId(core_window, Some(&raw mut process_id));
+    }
+
+    Ok(Some(ModernAppWindow {
+        frame,
+        core_window,
+        process_id,
+    }))
+}
+
+fn has_class(hwnd: HWND, class: &str) -> bool {
+    if hwnd.is_invalid() {
+        return false;
+    }
+
+    // Window class names are limited to 256 characters.
+    let mut buffer = [0u16; 257];
+
+    // SAFETY: `buffer` is a valid, writable slice.
+    let length = unsafe { GetClassNameW(hwnd, &mut buffer) };
+
+    length > 0 && String::from_utf16_lossy(&buffer[..length as usize]) == class
+}
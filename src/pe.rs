@@ -0,0 +1,256 @@
+//! Provides a minimal parser for the export table of PE images and the decoding of syscall stubs.
+
+use std::io::{Error, ErrorKind, Result};
+
+pub(crate) const IMAGE_FILE_MACHINE_I386: u16 = 0x014C;
+pub(crate) const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+pub(crate) const IMAGE_FILE_MACHINE_ARM64: u16 = 0xAA64;
+
+/// How the sections of an image are laid out in the underlying buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Layout {
+    /// The image as stored on disk, with sections at their raw file offsets.
+    File,
+    /// The image as mapped by the loader, with sections at their relative virtual addresses.
+    Mapped,
+}
+
+/// A single named export of an image.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Export<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) rva: u32,
+    /// Whether the export is forwarded to another module, in which case `rva` points to the forwarder string.
+    pub(crate) forwarded: bool,
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+}
+
+/// A PE image in a byte buffer.
+pub(crate) struct Image<'a> {
+    data: &'a [u8],
+    layout: Layout,
+    machine: u16,
+    export_directory: (u32, u32),
+    sections: Vec<Section>,
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid("unexpected end of image"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid("unexpected end of image"))
+}
+
+impl<'a> Image<'a> {
+    pub(crate) fn parse(data: &'a [u8], layout: Layout) -> Result<Self> {
+        if data.get(..2) != Some(b"MZ") {
+            return Err(invalid("missing DOS header"));
+        }
+
+        let nt_headers = read_u32(data, 0x3C)? as usize;
+
+        if data.get(nt_headers..nt_headers + 4) != Some(b"PE\0\0") {
+            return Err(invalid("missing PE signature"));
+        }
+
+        let file_header = nt_headers + 4;
+        let machine = read_u16(data, file_header)?;
+        let number_of_sections = read_u16(data, file_header + 2)? as usize;
+        let size_of_optional_header = read_u16(data, file_header + 16)? as usize;
+
+        let optional_header = file_header + 20;
+        let data_directories = match read_u16(data, optional_header)? {
+            0x10B => optional_header + 96,
+            0x20B => optional_header + 112,
+            _ => return Err(invalid("unknown optional header magic")),
+        };
+
+        let export_directory = (
+            read_u32(data, data_directories)?,
+            read_u32(data, data_directories + 4)?,
+        );
+
+        let section_headers = optional_header + size_of_optional_header;
+        let sections = (0..number_of_sections)
+            .map(|index| {
+                let header = section_headers + index * 40;
+
+                Ok(Section {
+                    virtual_size: read_u32(data, header + 8)?.max(read_u32(data, header + 16)?),
+                    virtual_address: read_u32(data, header + 12)?,
+                    raw_offset: read_u32(data, header + 20)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            data,
+            layout,
+            machine,
+            export_directory,
+            sections,
+        })
+    }
+
+    /// Creates an image from the module mapped by the loader at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must be the base address of a loaded module that stays loaded for `'a`.
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) unsafe fn from_module(base: *const u8) -> Result<Self> {
+        // SAFETY: The DOS header of a loaded module is mapped.
+        let nt_headers = unsafe { base.add(0x3C).cast::<u32>().read_unaligned() } as usize;
+        // SAFETY: The NT headers of a loaded module are mapped. `SizeOfImage` is at the same offset in PE32 and PE32+.
+        let size_of_image =
+            unsafe { base.add(nt_headers + 80).cast::<u32>().read_unaligned() } as usize;
+
+        // SAFETY: The loader maps the entire image.
+        Self::parse(
+            unsafe { std::slice::from_raw_parts(base, size_of_image) },
+            Layout::Mapped,
+        )
+    }
+
+    /// Returns the `IMAGE_FILE_MACHINE_*` value of the image.
+    pub(crate) fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    fn offset(&self, rva: u32) -> usize {
+        if self.layout == Layout::Mapped {
+            return rva as usize;
+        }
+
+        match self.sections.iter().find(|section| {
+            (section.virtual_address..section.virtual_address.saturating_add(section.virtual_size))
+                .contains(&rva)
+        }) {
+            Some(section) => (rva - section.virtual_address + section.raw_offset) as usize,
+            None => rva as usize,
+        }
+    }
+
+    /// Returns up to `length` bytes starting at `rva`.
+    pub(crate) fn bytes(&self, rva: u32, length: usize) -> Result<&'a [u8]> {
+        let offset = self.offset(rva);
+
+        self.data
+            .get(offset..offset.saturating_add(length).min(self.data.len()))
+            .ok_or_else(|| invalid("RVA out of bounds"))
+    }
+
+    fn c_str(&self, rva: u32) -> Result<&'a str> {
+        let offset = self.offset(rva);
+        let bytes = self
+            .data
+            .get(offset..)
+            .ok_or_else(|| invalid("RVA out of bounds"))?;
+        let end = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid("unterminated string"))?;
+
+        std::str::from_utf8(&bytes[..end]).map_err(|_| invalid("export name is not valid UTF-8"))
+    }
+
+    /// Returns all named exports of the image.
+    pub(crate) fn exports(&self) -> Result<Vec<Export<'a>>> {
+        let (directory_rva, directory_size) = self.export_directory;
+
+        if directory_rva == 0 {
+            return Ok(Vec::new());
+        }
+
+        let directory = self.offset(directory_rva);
+        let number_of_functions = read_u32(self.data, directory + 20)?;
+        let number_of_names = read_u32(self.data, directory + 24)? as usize;
+        let functions = self.offset(read_u32(self.data, directory + 28)?);
+        let names = self.offset(read_u32(self.data, directory + 32)?);
+        let ordinals = self.offset(read_u32(self.data, directory + 36)?);
+
+        (0..number_of_names)
+            .map(|index| {
+                let name = self.c_str(read_u32(self.data, names + index * 4)?)?;
+                let ordinal = read_u16(self.data, ordinals + index * 2)? as u32;
+
+                if ordinal >= number_of_functions {
+                    return Err(invalid("export ordinal out of range"));
+                }
+
+                let rva = read_u32(self.data, functions + ordinal as usize * 4)?;
+
+                Ok(Export {
+                    name,
+                    rva,
+                    forwarded: (directory_rva..directory_rva.saturating_add(directory_size))
+                        .contains(&rva),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decodes the syscall number from the code of a syscall stub for `machine`.
+///
+/// - x86-64: `mov r10, rcx; mov eax, imm32`
+/// - x86: `mov eax, imm32`
+/// - ARM64: `svc #imm16`
+pub(crate) fn syscall_number(machine: u16, code: &[u8]) -> Option<u32> {
+    match (machine, code) {
+        (IMAGE_FILE_MACHINE_AMD64, [0x4C, 0x8B, 0xD1, 0xB8, a, b, c, d, ..])
+        | (IMAGE_FILE_MACHINE_I386, [0xB8, a, b, c, d, ..]) => {
+            Some(u32::from_le_bytes([*a, *b, *c, *d]))
+        }
+        (IMAGE_FILE_MACHINE_ARM64, [a, b, c, d, ..]) => {
+            let instruction = u32::from_le_bytes([*a, *b, *c, *d]);
+
+            (instruction & 0xFFE0_001F == 0xD400_0001).then_some((instruction >> 5) & 0xFFFF)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pe::{
+        syscall_number, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_I386,
+    };
+
+    #[test]
+    pub fn syscall_number_should_decode_stubs() {
+        assert_eq!(
+            syscall_number(
+                IMAGE_FILE_MACHINE_AMD64,
+                &[0x4C, 0x8B, 0xD1, 0xB8, 0x05, 0x10, 0x00, 0x00, 0xF6]
+            ),
+            Some(0x1005)
+        );
+        assert_eq!(
+            syscall_number(IMAGE_FILE_MACHINE_I386, &[0xB8, 0x05, 0x10, 0x00, 0x00]),
+            Some(0x1005)
+        );
+        assert_eq!(
+            syscall_number(IMAGE_FILE_MACHINE_ARM64, &0xD402_00A1u32.to_le_bytes()),
+            Some(0x1005)
+        );
+        assert_eq!(
+            syscall_number(IMAGE_FILE_MACHINE_AMD64, &[0xE9, 0x00, 0x00, 0x00, 0x00]),
+            None
+        );
+    }
+}
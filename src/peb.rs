@@ -0,0 +1,189 @@
+//! Provides access to the process environment block, for locating loaded modules, their exports and the operating
+//! system version without importing any functions.
+//!
+//! Without the `std` feature, this replaces `GetModuleHandleW`, `GetProcAddress` and `RtlGetVersion`.
+
+use core::arch::asm;
+use core::ffi::{c_void, CStr};
+
+use crate::version::OsBuild;
+
+#[repr(C)]
+struct ListEntry {
+    flink: *const ListEntry,
+    _blink: *const ListEntry,
+}
+
+#[repr(C)]
+struct UnicodeString {
+    length: u16,
+    _maximum_length: u16,
+    buffer: *const u16,
+}
+
+/// The leading fields of `LDR_DATA_TABLE_ENTRY`, which are identical on all supported operating systems.
+#[repr(C)]
+struct LdrDataTableEntry {
+    in_load_order_links: ListEntry,
+    _in_memory_order_links: ListEntry,
+    _in_initialization_order_links: ListEntry,
+    dll_base: *const u8,
+    _entry_point: *const c_void,
+    _size_of_image: u32,
+    _full_dll_name: UnicodeString,
+    base_dll_name: UnicodeString,
+}
+
+/// The leading fields of `PEB_LDR_DATA`.
+#[repr(C)]
+struct PebLdrData {
+    _reserved: [u8; 8],
+    _ss_handle: *const c_void,
+    in_load_order_module_list: ListEntry,
+}
+
+/// The leading fields of `PEB`.
+#[repr(C)]
+struct Peb {
+    _reserved1: [u8; 4],
+    _reserved2: [*const c_void; 2],
+    ldr: *const PebLdrData,
+}
+
+/// The offset of `OSMajorVersion` in `PEB`, which is followed by `OSMinorVersion` and `OSBuildNumber`.
+#[cfg(target_pointer_width = "64")]
+const OS_VERSION_OFFSET: usize = 0x118;
+#[cfg(target_pointer_width = "32")]
+const OS_VERSION_OFFSET: usize = 0xA4;
+
+fn peb() -> *const Peb {
+    let peb: *const Peb;
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: `gs:[0x60]` holds the address of the PEB in the TEB of every thread.
+    unsafe {
+        asm!("mov {}, gs:[0x60]", out(reg) peb, options(nostack, readonly, preserves_flags));
+    }
+
+    #[cfg(target_arch = "x86")]
+    // SAFETY: `fs:[0x30]` holds the address of the PEB in the TEB of every thread.
+    unsafe {
+        asm!("mov {}, fs:[0x30]", out(reg) peb, options(nostack, readonly, preserves_flags));
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: `x18` holds the address of the TEB, which holds the address of the PEB at offset 0x60.
+    unsafe {
+        asm!("ldr {}, [x18, #0x60]", out(reg) peb, options(nostack, readonly, preserves_flags));
+    }
+
+    peb
+}
+
+/// Returns the version and build number of the operating system, as stored in the PEB.
+pub(crate) fn os_build() -> OsBuild {
+    // SAFETY: The PEB is always mapped and contains the version fields at `OS_VERSION_OFFSET`.
+    unsafe {
+        let version = peb().cast::<u8>().add(OS_VERSION_OFFSET);
+
+        OsBuild {
+            major: version.cast::<u32>().read(),
+            minor: version.add(4).cast::<u32>().read(),
+            build: version.add(8).cast::<u16>().read() as u32,
+        }
+    }
+}
+
+/// Returns whether the current process is a 32-bit process running on a 64-bit operating system.
+#[cfg(target_arch = "x86")]
+pub(crate) fn is_wow64() -> bool {
+    let wow32_reserved: usize;
+
+    // SAFETY: `fs:[0xC0]` holds `WOW32Reserved` in the TEB of every thread, which is only set under WOW64.
+    unsafe {
+        asm!("mov {}, fs:[0xC0]", out(reg) wow32_reserved, options(nostack, readonly, preserves_flags));
+    }
+
+    wow32_reserved != 0
+}
+
+/// Returns the base address of the loaded module whose file name equals `name`, ignoring ASCII case.
+pub(crate) fn module(name: &str) -> Option<*const u8> {
+    // SAFETY: The loader data and its module list are valid for the lifetime of the process. Entries are only unlinked
+    // when a module is unloaded, which the modules looked up here never are.
+    unsafe {
+        let head = &raw const (*(*peb()).ldr).in_load_order_module_list;
+        let mut link = (*head).flink;
+
+        while link != head {
+            let entry = link.cast::<LdrDataTableEntry>();
+            let base_dll_name = &(*entry).base_dll_name;
+            let base_dll_name = core::slice::from_raw_parts(
+                base_dll_name.buffer,
+                base_dll_name.length as usize / 2,
+            );
+
+            if base_dll_name.len() == name.len()
+                && base_dll_name
+                    .iter()
+                    .zip(name.bytes())
+                    .all(|(&a, b)| a < 0x80 && (a as u8).eq_ignore_ascii_case(&b))
+            {
+                return Some((*entry).dll_base);
+            }
+
+            link = (*entry).in_load_order_links.flink;
+        }
+    }
+
+    None
+}
+
+/// Returns the address of the export `name` of the module mapped at `base`. Forwarded exports are not resolved.
+///
+/// # Safety
+///
+/// `base` must be the base address of a loaded module.
+pub(crate) unsafe fn export(base: *const u8, name: &CStr) -> Option<*const c_void> {
+    // SAFETY: The headers and the export directory of a loaded module are mapped, and all RVAs in them are in bounds.
+    unsafe {
+        let read_u32 = |offset: usize| base.add(offset).cast::<u32>().read_unaligned() as usize;
+
+        let optional_header = read_u32(0x3C) + 24;
+        let data_directories = match base.add(optional_header).cast::<u16>().read_unaligned() {
+            0x10B => optional_header + 96,
+            0x20B => optional_header + 112,
+            _ => return None,
+        };
+
+        let directory = read_u32(data_directories);
+        let directory_size = read_u32(data_directories + 4);
+
+        if directory == 0 {
+            return None;
+        }
+
+        let number_of_names = read_u32(directory + 24);
+        let functions = read_u32(directory + 28);
+        let names = read_u32(directory + 32);
+        let ordinals = read_u32(directory + 36);
+
+        // Export names are sorted, as the loader performs the same binary search.
+        let names = core::slice::from_raw_parts(base.add(names).cast::<u32>(), number_of_names);
+        let index = names
+            .binary_search_by(|&rva| CStr::from_ptr(base.add(rva as usize).cast()).cmp(name))
+            .ok()?;
+
+        let ordinal = base
+            .add(ordinals + index * 2)
+            .cast::<u16>()
+            .read_unaligned() as usize;
+        let function = read_u32(functions + ordinal * 4);
+
+        if (directory..directory + directory_size).contains(&function) {
+            return None;
+        }
+
+        Some(base.add(function).cast())
+    }
+}
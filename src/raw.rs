@@ -0,0 +1,136 @@
+//! Provides invoking the functions in [`crate::functions`] with raw values, which is available without the `std`
+//! feature.
+//!
+//! Parameters are passed as `usize` in declaration order, and the raw return value is returned without converting it
+//! into the return type of the function. Calls always invoke the syscalls, even if a backend is installed via
+//! `crate::backend`. With a `pin-*` feature enabled, the indices of the pinned version are used.
+//!
+//! With the `unsafe-direct` feature enabled, `call_no_param` and its siblings additionally invoke `apfnSimpleCall`
+//! entries by raw index, e.g. for entries missing from [`NtUserCall`] because they only exist on some editions or
+//! builds. As with [`crate::functions::user_call`], the index is passed as is and must match the running build.
+
+use core::ffi::c_void;
+use core::mem::transmute;
+
+use crate::{
+    error::UserCallError,
+    functions::{direct_syscall, dispatched, instrumented, NtUserCall},
+    indices::pinned_index,
+    resolution::{index_resolution, resolved, resolved_family, Resolution},
+    version::PINNED_OS_VERSION,
+};
+
+/// Invokes `call` with `args`, returning the raw return value.
+///
+/// # Safety
+///
+/// `args` must satisfy the safety requirements of the function corresponding to `call`.
+///
+/// # Errors
+///
+/// Fails with [`UserCallError::InvalidArguments`] if the number of arguments does not match the number of parameters
+/// of the function.
+pub unsafe fn call(call: NtUserCall, args: &[usize]) -> Result<usize, UserCallError> {
+    instrumented!(Function(call), |dispatch| {
+        let family = call.family();
+
+        if args.len() != family.index_parameter() {
+            return Err(UserCallError::InvalidArguments {
+                expected: family.index_parameter(),
+                actual: args.len(),
+            });
+        }
+
+        let resolution = match PINNED_OS_VERSION {
+            Some(_) => match pinned_index(call) {
+                Some(index) => index_resolution(index, resolved_family(family)),
+                None => Resolution::Error(UserCallError::CallNotFound {
+                    export: None,
+                    last_error: None,
+                }),
+            },
+            None => resolved(call),
+        };
+
+        match resolution {
+            Resolution::Function(function) => {
+                dispatched!(dispatch = DedicatedSyscall);
+
+                type Fn0 = unsafe extern "system" fn() -> usize;
+                type Fn1 = unsafe extern "system" fn(usize) -> usize;
+                type Fn2 = unsafe extern "system" fn(usize, usize) -> usize;
+
+                // SAFETY: All parameters and return values are passed in integer registers or stack slots of at most
+                // pointer size, and the dedicated syscall takes `args.len()` parameters.
+                unsafe {
+                    Ok(match *args {
+                        [] => transmute::<*const c_void, Fn0>(function)(),
+                        [a] => transmute::<*const c_void, Fn1>(function)(a),
+                        [a, b] => transmute::<*const c_void, Fn2>(function)(a, b),
+                        _ => unreachable!("no function takes more than two parameters"),
+                    })
+                }
+            }
+            Resolution::Syscall(number) => {
+                dispatched!(dispatch = DirectSyscall { number });
+
+                // SAFETY: The caller upholds the safety requirements of the function, whose syscall takes `args.len()`
+                // parameters.
+                Ok(unsafe {
+                    match *args {
+                        [] => direct_syscall(number, []),
+                        [a] => direct_syscall(number, [a]),
+                        [a, b] => direct_syscall(number, [a, b]),
+                        _ => unreachable!("no function takes more than two parameters"),
+                    }
+                })
+            }
+            Resolution::Index(index, dispatch_family) => {
+                dispatched!(
+                    dispatch = UserCall {
+                        index: index as u32
+                    }
+                );
+
+                let mut family_args = [0; 3];
+                family_args[..args.len()].copy_from_slice(args);
+                family_args[args.len()] = index as usize;
+
+                // SAFETY: The caller upholds the safety requirements of the function with index `index`.
+                unsafe { family.call(dispatch_family, &family_args[..=args.len()]) }
+            }
+            Resolution::Error(error) => Err(error),
+        }
+    })
+}
+
+#[cfg(feature = "unsafe-direct")]
+macro_rules! raw_index_fn {
+    ( $($family:ident => $vis:vis fn $name:ident ($($paramname:ident),*);)+ ) => {
+        $(
+            #[doc = concat!("Invokes the `apfnSimpleCall` entry `index` via `", stringify!($family), "`.")]
+            ///
+            /// # Safety
+            ///
+            /// The parameters must satisfy the safety requirements of the entry with index `index` on the running build.
+            $vis unsafe fn $name(index: u32, $($paramname: usize),*) -> Result<usize, UserCallError> {
+                // SAFETY: The caller upholds the safety requirements of the entry.
+                unsafe { crate::functions::user_call::$family($($paramname,)* index) }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "unsafe-direct")]
+raw_index_fn! {
+    NtUserCallNoParam => pub fn call_no_param();
+    NtUserCallOneParam => pub fn call_one_param(param);
+    NtUserCallHwnd => pub fn call_hwnd(hwnd);
+    NtUserCallHwndSafe => pub fn call_hwnd_safe(hwnd);
+    NtUserCallHwndOpt => pub fn call_hwnd_opt(hwnd);
+    NtUserCallHwndParam => pub fn call_hwnd_param(hwnd, param);
+    NtUserCallHwndLock => pub fn call_hwnd_lock(hwnd);
+    NtUserCallHwndParamLock => pub fn call_hwnd_param_lock(hwnd, param);
+    NtUserCallHwndParamLockSafe => pub fn call_hwnd_param_lock_safe(hwnd, param);
+    NtUserCallTwoParam => pub fn call_two_param(param1, param2);
+}
@@ -0,0 +1,494 @@
+//! Provides the per-function resolution state of the functions in [`crate::functions`].
+//!
+//! Every function resolves how it is invoked exactly once, the first time it is called, and caches the outcome in a
+//! single atomic. Steady-state calls therefore only perform one atomic load to decide between the dedicated syscall,
+//! the `apfnSimpleCall` index and the cached error. Indices are stored next to the atomic together with how the
+//! `NtUserCall*` syscall is invoked, as are errors.
+//!
+//! With the `direct-syscalls` feature enabled or [`crate::context::ContextConfig::direct_syscalls`] set, the syscall
+//! number is decoded from the syscall stubs exported from win32u.dll on x86 and x64, and the syscall is invoked directly
+//! instead of via the stub, bypassing any user-mode hooks installed on it. Stubs that cannot be decoded because they
+//! have been patched are decoded from win32u.dll as stored on disk, and resolution fails if that is not possible
+//! either, rather than calling the patched stub.
+//!
+//! Exports are looked up via `GetModuleHandleW` and `GetProcAddress` with the `std` feature enabled, and by walking the
+//! module list in the PEB and the export directory of the module otherwise.
+
+use core::cell::UnsafeCell;
+use core::ffi::{c_void, CStr};
+use core::mem::MaybeUninit;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use crate::{
+    error::UserCallError,
+    functions::{Family, NtUserCall},
+    version::OsVersion,
+};
+#[cfg(not(feature = "std"))]
+use crate::{
+    indices::get_index_for,
+    version::{get_os_version, has_dedicated_syscalls},
+};
+
+/// How a function is invoked on the current operating system.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Resolution {
+    /// The function is a dedicated syscall exported from win32u.dll.
+    Function(*const c_void),
+    /// The function is invoked via the `NtUserCall*` family of syscalls with the given index, which is invoked as given.
+    Index(u16, FamilyDispatch),
+    /// The function is invoked directly via the syscall with the given number, decoded from its syscall stub.
+    Syscall(u16),
+    /// The function cannot be invoked.
+    Error(UserCallError),
+}
+
+/// How the `NtUserCall*` syscall is invoked for a function resolved to an index.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum FamilyDispatch {
+    /// The syscall is exported from win32u.dll or invoked via its built-in syscall stub.
+    Function(*const c_void),
+    /// The syscall is invoked directly via the given number, decoded from its syscall stub.
+    Syscall(u16),
+}
+
+impl From<FamilyDispatch> for Resolution {
+    fn from(value: FamilyDispatch) -> Self {
+        match value {
+            FamilyDispatch::Function(function) => Self::Function(function),
+            FamilyDispatch::Syscall(number) => Self::Syscall(number),
+        }
+    }
+}
+
+/// Combines `index` with the resolution of its `NtUserCall*` syscall, failing if the syscall cannot be invoked.
+pub(crate) fn index_resolution(index: u16, family: Resolution) -> Resolution {
+    match family {
+        Resolution::Function(function) => {
+            Resolution::Index(index, FamilyDispatch::Function(function))
+        }
+        Resolution::Syscall(number) => Resolution::Index(index, FamilyDispatch::Syscall(number)),
+        Resolution::Error(error) => Resolution::Error(error),
+        Resolution::Index(..) => unreachable!("syscall families are never resolved to an index"),
+    }
+}
+
+const UNRESOLVED: usize = 0;
+/// The resolution is being written to [`ResolutionCell`] by another thread.
+const WRITING: usize = 1;
+/// The resolution is stored in [`ResolutionCell`].
+const STORED: usize = 2;
+
+// Syscall numbers are stored as is, as the first 64 KiB of the address space are reserved and win32k syscall numbers
+// never collide with the states above. Indices and errors do not fit into a single value and are stored separately.
+impl Resolution {
+    fn encode(self) -> Option<usize> {
+        match self {
+            Self::Function(function) => Some(function as usize),
+            Self::Syscall(number) => Some(number as usize),
+            Self::Index(..) | Self::Error(_) => None,
+        }
+    }
+
+    fn decode(value: usize) -> Self {
+        if value <= u16::MAX as usize {
+            Self::Syscall(value as u16)
+        } else {
+            Self::Function(value as _)
+        }
+    }
+}
+
+/// The cached [`Resolution`] of a single function.
+pub(crate) struct ResolutionCell {
+    value: AtomicUsize,
+    /// Initialized once `value` is `STORED`.
+    resolution: UnsafeCell<MaybeUninit<Resolution>>,
+}
+
+// SAFETY: `resolution` is only written by the thread that transitioned `value` from `UNRESOLVED` to `WRITING`, and
+// only read after `value` has been set to `STORED`, after which it is never written again.
+unsafe impl Sync for ResolutionCell {}
+
+impl ResolutionCell {
+    pub(crate) const fn new() -> Self {
+        Self {
+            value: AtomicUsize::new(UNRESOLVED),
+            resolution: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Returns the cached resolution, calling `resolve` if the function has not been resolved yet.
+    ///
+    /// Concurrent first calls may both resolve the function; as resolution is deterministic, the first result is kept.
+    #[inline]
+    pub(crate) fn get_or_resolve(&self, resolve: impl FnOnce() -> Resolution) -> Resolution {
+        match self.value.load(Ordering::Relaxed) {
+            UNRESOLVED | WRITING => {
+                let resolution = resolve();
+                self.set(resolution);
+                resolution
+            }
+            STORED => {
+                fence(Ordering::Acquire);
+
+                // SAFETY: `resolution` has been initialized before `STORED` has been stored with release ordering.
+                unsafe { (*self.resolution.get()).assume_init() }
+            }
+            value => Resolution::decode(value),
+        }
+    }
+
+    fn set(&self, resolution: Resolution) {
+        match resolution.encode() {
+            Some(value) => {
+                _ = self.value.compare_exchange(
+                    UNRESOLVED,
+                    value,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+            }
+            None => {
+                if self
+                    .value
+                    .compare_exchange(UNRESOLVED, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    // SAFETY: This thread has exclusive access to `resolution` until `STORED` is stored.
+                    unsafe { (*self.resolution.get()).write(resolution) };
+                    self.value.store(STORED, Ordering::Release);
+                }
+            }
+        }
+    }
+}
+
+/// The cached resolutions of all functions, shared by [`crate::functions`] and [`crate::raw`]. With the `std` feature
+/// enabled, they are owned by [`crate::context::Context::global`] instead.
+#[cfg(not(feature = "std"))]
+static RESOLUTIONS: [ResolutionCell; NtUserCall::COUNT] =
+    [const { ResolutionCell::new() }; NtUserCall::COUNT];
+
+/// The cached resolutions of all `NtUserCall*` syscalls.
+#[cfg(not(feature = "std"))]
+static FAMILY_RESOLUTIONS: [ResolutionCell; Family::COUNT] =
+    [const { ResolutionCell::new() }; Family::COUNT];
+
+/// Returns the cached resolution of `call`, resolving it on the first call.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn resolved(call: NtUserCall) -> Resolution {
+    crate::context::Context::global().resolve(call)
+}
+
+/// Returns the cached resolution of `call`, resolving it on the first call.
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn resolved(call: NtUserCall) -> Resolution {
+    RESOLUTIONS[call as usize].get_or_resolve(|| resolve(call))
+}
+
+/// Returns the cached resolution of `family`, resolving it on the first call.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn resolved_family(family: Family) -> Resolution {
+    crate::context::Context::global().resolve_family(family)
+}
+
+/// Returns the cached resolution of `family`, resolving it on the first call.
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn resolved_family(family: Family) -> Resolution {
+    FAMILY_RESOLUTIONS[family as usize]
+        .get_or_resolve(|| family.resolve(get_os_version(), cfg!(feature = "direct-syscalls")))
+}
+
+/// Resolves how `call` is invoked on the current operating system.
+#[cfg(feature = "std")]
+pub(crate) fn resolve(call: NtUserCall) -> Resolution {
+    crate::context::Context::global().resolve_uncached(call)
+}
+
+/// Resolves how `call` is invoked on the current operating system.
+#[cfg(not(feature = "std"))]
+pub(crate) fn resolve(call: NtUserCall) -> Resolution {
+    let os_version = get_os_version();
+
+    resolve_with(
+        call,
+        has_dedicated_syscalls(),
+        cfg!(feature = "direct-syscalls"),
+        os_version,
+        || get_index_for(os_version, call),
+        resolved_family,
+    )
+}
+
+/// Resolves how `call` is invoked on `os_version`, invoking dedicated syscalls directly if `direct_syscalls`. Indices
+/// are looked up via `get_index` and combined with the resolution of their `NtUserCall*` syscall as returned by
+/// `resolve_family`.
+pub(crate) fn resolve_with(
+    call: NtUserCall,
+    dedicated_syscalls: bool,
+    direct_syscalls: bool,
+    os_version: Result<OsVersion, UserCallError>,
+    get_index: impl FnOnce() -> Option<u16>,
+    resolve_family: impl FnOnce(Family) -> Resolution,
+) -> Resolution {
+    let resolution = if dedicated_syscalls {
+        // Starting with Windows 11, NtUserCall* has been replaced with dedicated syscalls in win32u.
+        match find_export(Library::Win32u, call.export_name()) {
+            Ok(function) => resolve_stub(function, call.export_name(), direct_syscalls),
+            Err(error) => Resolution::Error(error),
+        }
+    } else {
+        match get_index() {
+            Some(index) => index_resolution(index, resolve_family(call.family())),
+            None => match os_version {
+                Err(error @ UserCallError::UnsupportedEnvironment { .. }) => {
+                    Resolution::Error(error)
+                }
+                _ => Resolution::Error(UserCallError::CallNotFound {
+                    export: None,
+                    last_error: None,
+                }),
+            },
+        }
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(?call, ?resolution, "resolved function");
+
+    resolution
+}
+
+/// Resolves the syscall stub `function` of `export` to its syscall number if `direct_syscalls`.
+pub(crate) fn resolve_stub(
+    function: *const c_void,
+    export: &'static CStr,
+    direct_syscalls: bool,
+) -> Resolution {
+    if !direct_syscalls {
+        return Resolution::Function(function);
+    }
+
+    match decode_syscall_number(function, export) {
+        Ok(number) => Resolution::Syscall(number),
+        Err(error) => Resolution::Error(error),
+    }
+}
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+const MACHINE: u16 = crate::pe::IMAGE_FILE_MACHINE_AMD64;
+#[cfg(all(feature = "std", target_arch = "x86"))]
+const MACHINE: u16 = crate::pe::IMAGE_FILE_MACHINE_I386;
+
+/// Decodes the syscall number of `export` from its stub `function` or, if the stub has been patched, from win32u.dll
+/// as stored on disk. The stub is never called instead, as that would not bypass the hooks installed on it.
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn decode_syscall_number(
+    function: *const c_void,
+    export: &'static CStr,
+) -> Result<u16, UserCallError> {
+    // Native 32-bit kernels use different syscall stubs, which `direct_syscall` does not emulate.
+    #[cfg(target_arch = "x86")]
+    if !crate::version::is_wow64() {
+        return Err(UserCallError::ArchitectureNotSupported);
+    }
+
+    // SAFETY: Syscall stubs are longer than the decoded instructions.
+    let code = unsafe { core::slice::from_raw_parts(function.cast::<u8>(), 8) };
+
+    if let Some(number) = decode_stub(code) {
+        return Ok(number);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        ?export,
+        "could not decode syscall stub, decoding it from win32u.dll on disk"
+    );
+
+    file_syscall_number(export).ok_or(UserCallError::SyscallNotDecoded { export })
+}
+
+/// Syscalls are only invoked directly on x86 and x64, as ARM64 encodes the syscall number as an immediate of `svc`.
+#[cfg(not(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86"))))]
+fn decode_syscall_number(
+    _function: *const c_void,
+    _export: &'static CStr,
+) -> Result<u16, UserCallError> {
+    Err(UserCallError::ArchitectureNotSupported)
+}
+
+/// Decodes the win32k syscall number from the syscall stub `code`.
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn decode_stub(code: &[u8]) -> Option<u16> {
+    /// The lowest win32k syscall number, as win32k syscalls are dispatched via the second service table.
+    const MIN_SYSCALL: u16 = 0x1000;
+
+    crate::pe::syscall_number(MACHINE, code)
+        .and_then(|number| u16::try_from(number).ok())
+        .filter(|&number| number >= MIN_SYSCALL)
+}
+
+/// Decodes the syscall number of `export` from win32u.dll as stored on disk, which is read on the first call.
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
+fn file_syscall_number(export: &CStr) -> Option<u16> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt, sync::OnceLock};
+
+    use windows::{
+        core::w,
+        Win32::{
+            Foundation::MAX_PATH,
+            System::LibraryLoader::{GetModuleFileNameW, GetModuleHandleW},
+        },
+    };
+
+    use crate::pe::{Image, Layout};
+
+    static WIN32U: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+    let data = WIN32U.get_or_init(|| {
+        // SAFETY: `w!` returns a valid null-terminated string.
+        let module = unsafe { GetModuleHandleW(w!("win32u")) }.ok()?;
+        let mut path = [0u16; MAX_PATH as usize];
+
+        // SAFETY: `module` is a loaded module and `path` is valid for writes of its length.
+        let length = unsafe { GetModuleFileNameW(Some(module), &mut path) } as usize;
+
+        if length == 0 || length == path.len() {
+            return None;
+        }
+
+        std::fs::read(OsString::from_wide(&path[..length])).ok()
+    });
+
+    let image = Image::parse(data.as_deref()?, Layout::File).ok()?;
+
+    if image.machine() != MACHINE {
+        return None;
+    }
+
+    let name = export.to_str().ok()?;
+    let export = image
+        .exports()
+        .ok()?
+        .into_iter()
+        .find(|export| export.name == name && !export.forwarded)?;
+
+    decode_stub(image.bytes(export.rva, 8).ok()?)
+}
+
+/// A module exporting syscalls.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Library {
+    Ntdll,
+    User32,
+    Win32u,
+}
+
+impl Library {
+    /// Returns the file name of this library.
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::Ntdll => "ntdll.dll",
+            Self::User32 => "user32.dll",
+            Self::Win32u => "win32u.dll",
+        }
+    }
+}
+
+/// Returns the address of the export `export` of `library`, which must be loaded.
+#[cfg(feature = "std")]
+pub(crate) fn find_export(
+    library: Library,
+    export: &'static CStr,
+) -> Result<*const c_void, UserCallError> {
+    use windows::{
+        core::{w, PCSTR},
+        Win32::{
+            Foundation::GetLastError,
+            System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+        },
+    };
+
+    let name = match library {
+        Library::Ntdll => w!("ntdll"),
+        Library::User32 => w!("user32"),
+        Library::Win32u => w!("win32u"),
+    };
+
+    // SAFETY: `name` is a valid null-terminated string; on success, GetModuleHandleW returns a valid module handle.
+    let module = unsafe { GetModuleHandleW(name) }.map_err(|_| UserCallError::LibraryNotFound {
+        library: library.name(),
+        // SAFETY: `GetLastError` has no preconditions.
+        last_error: Some(unsafe { GetLastError() }.0),
+    })?;
+
+    // SAFETY: `export` is a valid null-terminated string; GetProcAddress returns a valid function pointer if the
+    // function exists.
+    match unsafe { GetProcAddress(module, PCSTR(export.as_ptr().cast())) } {
+        Some(function) => Ok(function as _),
+        None => Err(UserCallError::CallNotFound {
+            export: Some(export),
+            // SAFETY: `GetLastError` has no preconditions.
+            last_error: Some(unsafe { GetLastError() }.0),
+        }),
+    }
+}
+
+/// Returns the address of the export `export` of `library`, which must be loaded.
+#[cfg(not(feature = "std"))]
+pub(crate) fn find_export(
+    library: Library,
+    export: &'static CStr,
+) -> Result<*const c_void, UserCallError> {
+    let module = crate::peb::module(library.name()).ok_or(UserCallError::LibraryNotFound {
+        library: library.name(),
+        last_error: None,
+    })?;
+
+    // SAFETY: `module` is the base address of a loaded module.
+    unsafe { crate::peb::export(module, export) }.ok_or(UserCallError::CallNotFound {
+        export: Some(export),
+        last_error: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::UserCallError,
+        resolution::{FamilyDispatch, Resolution, ResolutionCell},
+        version::OsBuild,
+    };
+
+    #[test]
+    pub fn resolution_cell_should_cache_resolution() {
+        for resolution in [
+            Resolution::Index(0, FamilyDispatch::Function(0x7FF8_1234usize as _)),
+            Resolution::Index(u16::MAX, FamilyDispatch::Syscall(0x1005)),
+            Resolution::Syscall(0x1000),
+            Resolution::Syscall(0x14B2),
+            Resolution::Error(UserCallError::OsNotSupported {
+                os_build: OsBuild {
+                    major: 6,
+                    minor: 0,
+                    build: 6002,
+                },
+            }),
+            Resolution::Error(UserCallError::LibraryNotFound {
+                library: "win32u.dll",
+                last_error: Some(126),
+            }),
+            Resolution::Function(0x7FF8_1234usize as _),
+        ] {
+            let cell = ResolutionCell::new();
+
+            assert_eq!(cell.get_or_resolve(|| resolution), resolution);
+            assert_eq!(cell.get_or_resolve(|| unreachable!()), resolution);
+        }
+    }
+}
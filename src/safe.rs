@@ -0,0 +1,253 @@
+//! Provides safe wrappers for the functions in [`crate::functions`] that are sound to call with any arguments.
+//!
+//! Handles created by the wrappers are returned as [`Owned`] handles, which destroy them when dropped unless released
+//! via [`into_raw`]. `BOOL` returns are converted into [`windows::core::Result`], capturing the error from
+//! `GetLastError` on failure, and [`UserCallError`](crate::error::UserCallError)s are converted into the corresponding
+//! [`windows::core::Error`].
+
+use windows::{
+    core::{Error, Free, Owned, Result},
+    Win32::{
+        Foundation::{BOOL, HWND, POINT},
+        UI::{
+            Input::KeyboardAndMouse::HKL,
+            WindowsAndMessaging::{HMENU, MESSAGEBOX_STYLE},
+        },
+    },
+};
+
+use crate::functions::{
+    refs, NtUserAllowSetForegroundWindow, NtUserCreateMenu, NtUserCreatePopupMenu,
+    NtUserDestroyCaret, NtUserDrawMenuBar, NtUserEnableWindow, NtUserGetKeyboardLayout,
+    NtUserGetKeyboardType, NtUserGetMessagePos, NtUserGetQueueStatus,
+    NtUserGetUnpredictedMessagePos, NtUserLockSetForegroundWindow, NtUserMessageBeep,
+    NtUserPostQuitMessage, NtUserReleaseCapture, NtUserSetCaretBlinkTime, NtUserSetCaretPos,
+    NtUserSetCursorPos, NtUserSetDoubleClickTime, NtUserSetPhysicalCursorPos,
+    NtUserSetProcessDefaultLayout, NtUserShowCursor, NtUserSwapMouseButton, NtUserUpdateWindow,
+};
+
+/// `LSFW_LOCK`
+const LSFW_LOCK: u32 = 1;
+/// `LSFW_UNLOCK`
+const LSFW_UNLOCK: u32 = 2;
+
+fn owned_menu(menu: HMENU) -> Result<Owned<HMENU>> {
+    if menu.is_invalid() {
+        return Err(Error::from_win32());
+    }
+
+    // SAFETY: The menu has just been created and is owned by the caller.
+    Ok(unsafe { Owned::new(menu) })
+}
+
+fn point_from_message_pos(pos: u32) -> POINT {
+    POINT {
+        x: pos as i16 as i32,
+        y: (pos >> 16) as i16 as i32,
+    }
+}
+
+/// Creates a menu, which is destroyed when dropped. Release it via [`into_raw`] before assigning it to a window or
+/// inserting it into another menu, which then own it.
+///
+/// See [`crate::functions::NtUserCreateMenu`].
+pub fn create_menu() -> Result<Owned<HMENU>> {
+    // SAFETY: `NtUserCreateMenu` takes no parameters.
+    owned_menu(unsafe { NtUserCreateMenu() }?)
+}
+
+/// Creates a popup menu, which is destroyed when dropped. Release it via [`into_raw`] before inserting it into another
+/// menu, which then owns it.
+///
+/// See [`crate::functions::NtUserCreatePopupMenu`].
+pub fn create_popup_menu() -> Result<Owned<HMENU>> {
+    // SAFETY: `NtUserCreatePopupMenu` takes no parameters.
+    owned_menu(unsafe { NtUserCreatePopupMenu() }?)
+}
+
+/// Releases ownership of `handle` without destroying it, for passing it on to an owner such as a window.
+pub fn into_raw<T: Free + Copy>(handle: Owned<T>) -> T {
+    let raw = *handle;
+    core::mem::forget(handle);
+    raw
+}
+
+/// `GetCursorPos` `which` value for the regular cursor position.
+const CURSOR_POS_REGULAR: u32 = 1;
+/// `GetCursorPos` `which` value for the cursor position in the DPI awareness context of the current thread.
+const CURSOR_POS_LOGICAL: u32 = 2;
+
+fn get_cursor_pos_with(which: u32) -> Result<POINT> {
+    let mut point = POINT::default();
+
+    // SAFETY: `point` is valid for writes and `which` selects a supported coordinate space.
+    unsafe { refs::NtUserGetCursorPos(&mut point, which) }?.ok()?;
+    Ok(point)
+}
+
+/// Returns the cursor position in screen coordinates.
+pub fn get_cursor_pos() -> Result<POINT> {
+    get_cursor_pos_with(CURSOR_POS_REGULAR)
+}
+
+/// Returns the cursor position in screen coordinates, scaled for the DPI awareness context of the current thread.
+pub fn get_logical_cursor_pos() -> Result<POINT> {
+    get_cursor_pos_with(CURSOR_POS_LOGICAL)
+}
+
+/// Returns the cursor position of the last message retrieved by the current thread, in screen coordinates.
+pub fn get_message_pos() -> Result<POINT> {
+    // SAFETY: `NtUserGetMessagePos` takes no parameters.
+    Ok(point_from_message_pos(unsafe { NtUserGetMessagePos() }?))
+}
+
+/// Returns the cursor position of the last message retrieved by the current thread, without pointer prediction.
+pub fn get_unpredicted_message_pos() -> Result<POINT> {
+    // SAFETY: `NtUserGetUnpredictedMessagePos` takes no parameters.
+    Ok(point_from_message_pos(unsafe {
+        NtUserGetUnpredictedMessagePos()
+    }?))
+}
+
+/// Moves the caret of the current thread to `x`, `y` in client coordinates.
+pub fn set_caret_pos(x: i32, y: i32) -> Result<()> {
+    // SAFETY: The caret position is validated by the kernel.
+    unsafe { NtUserSetCaretPos(x, y) }?.ok()
+}
+
+/// Destroys the caret of the current thread.
+pub fn destroy_caret() -> Result<()> {
+    // SAFETY: `NtUserDestroyCaret` takes no parameters.
+    unsafe { NtUserDestroyCaret() }?.ok()
+}
+
+/// Sets the caret blink time in milliseconds.
+pub fn set_caret_blink_time(milliseconds: u32) -> Result<()> {
+    // SAFETY: The blink time is validated by the kernel.
+    unsafe { NtUserSetCaretBlinkTime(milliseconds) }?.ok()
+}
+
+/// Moves the cursor to `x`, `y` in screen coordinates.
+pub fn set_cursor_pos(x: i32, y: i32) -> Result<()> {
+    // SAFETY: The cursor position is validated by the kernel.
+    unsafe { NtUserSetCursorPos(x, y) }?.ok()
+}
+
+/// Moves the cursor to `x`, `y` in physical screen coordinates.
+pub fn set_physical_cursor_pos(x: i32, y: i32) -> Result<()> {
+    // SAFETY: The cursor position is validated by the kernel.
+    unsafe { NtUserSetPhysicalCursorPos(x, y) }?.ok()
+}
+
+/// Increments or decrements the cursor display counter, returning the new counter.
+pub fn show_cursor(show: bool) -> Result<i32> {
+    // SAFETY: `NtUserShowCursor` takes a plain flag.
+    Ok(unsafe { NtUserShowCursor(show.into()) }?)
+}
+
+/// Releases the mouse capture of the current thread.
+pub fn release_capture() -> Result<()> {
+    // SAFETY: `NtUserReleaseCapture` takes no parameters.
+    unsafe { NtUserReleaseCapture() }?.ok()
+}
+
+/// Plays the waveform sound for `style`.
+pub fn message_beep(style: MESSAGEBOX_STYLE) -> Result<()> {
+    // SAFETY: The style is validated by the kernel.
+    unsafe { NtUserMessageBeep(style) }?.ok()
+}
+
+/// Swaps or restores the meaning of the left and right mouse buttons, returning whether they were swapped before.
+pub fn swap_mouse_button(swap: bool) -> Result<bool> {
+    // SAFETY: `NtUserSwapMouseButton` takes a plain flag.
+    Ok(unsafe { NtUserSwapMouseButton(swap.into()) }?.as_bool())
+}
+
+/// Sets the double-click time in milliseconds. `0` restores the default.
+pub fn set_double_click_time(milliseconds: u32) -> Result<()> {
+    // SAFETY: The double-click time is validated by the kernel.
+    unsafe { NtUserSetDoubleClickTime(milliseconds) }?.ok()
+}
+
+/// Returns the `QS_*` flags of the messages in the queue of the current thread, filtered by `flags`.
+pub fn get_queue_status(flags: u32) -> Result<u32> {
+    // SAFETY: The flags are validated by the kernel.
+    Ok(unsafe { NtUserGetQueueStatus(flags) }?)
+}
+
+/// Returns information about the keyboard, as selected by `type_flag`.
+pub fn get_keyboard_type(type_flag: i32) -> Result<i32> {
+    // SAFETY: The type flag is validated by the kernel.
+    Ok(unsafe { NtUserGetKeyboardType(type_flag) }?)
+}
+
+/// Returns the active keyboard layout of the thread `thread_id`, or of the current thread if `0`.
+pub fn get_keyboard_layout(thread_id: u32) -> Result<HKL> {
+    // SAFETY: The thread id is validated by the kernel.
+    Ok(unsafe { NtUserGetKeyboardLayout(thread_id) }?)
+}
+
+/// Allows the process `process_id`, or any process if `ASFW_ANY`, to set the foreground window.
+pub fn allow_set_foreground_window(process_id: u32) -> Result<()> {
+    // SAFETY: The process id is validated by the kernel.
+    BOOL(unsafe { NtUserAllowSetForegroundWindow(process_id) }? as i32).ok()
+}
+
+/// Disables or enables calls to `SetForegroundWindow`.
+pub fn lock_set_foreground_window(lock: bool) -> Result<()> {
+    let code = if lock { LSFW_LOCK } else { LSFW_UNLOCK };
+
+    // SAFETY: `code` is a valid lock code.
+    unsafe { NtUserLockSetForegroundWindow(code) }?.ok()
+}
+
+/// Posts `WM_QUIT` with `exit_code` to the queue of the current thread.
+pub fn post_quit_message(exit_code: i32) -> Result<()> {
+    // SAFETY: `NtUserPostQuitMessage` takes a plain exit code.
+    Ok(unsafe { NtUserPostQuitMessage(exit_code) }?)
+}
+
+/// Returns the default layout of windows created by the current process.
+pub fn get_process_default_layout() -> Result<u32> {
+    let mut layout = 0;
+
+    // SAFETY: `layout` is valid for writes.
+    unsafe { refs::NtUserGetProcessDefaultLayout(&mut layout) }?.ok()?;
+    Ok(layout)
+}
+
+/// Sets the default layout of windows created by the current process.
+pub fn set_process_default_layout(layout: u32) -> Result<()> {
+    // SAFETY: The layout is validated by the kernel.
+    unsafe { NtUserSetProcessDefaultLayout(layout) }?.ok()
+}
+
+/// Redraws the menu bar of `hwnd`.
+pub fn draw_menu_bar(hwnd: HWND) -> Result<()> {
+    // SAFETY: Window handles are validated by the kernel.
+    unsafe { NtUserDrawMenuBar(hwnd) }?.ok()
+}
+
+/// Sends `WM_PAINT` to `hwnd` if its update region is not empty.
+pub fn update_window(hwnd: HWND) -> Result<()> {
+    // SAFETY: Window handles are validated by the kernel.
+    unsafe { NtUserUpdateWindow(hwnd) }?.ok()
+}
+
+/// Enables or disables input to `hwnd`, returning whether it was disabled before.
+pub fn enable_window(hwnd: HWND, enable: bool) -> Result<bool> {
+    // SAFETY: Window handles are validated by the kernel.
+    Ok(unsafe { NtUserEnableWindow(hwnd, enable.into()) }?.as_bool())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::safe::point_from_message_pos;
+
+    #[test]
+    pub fn point_from_message_pos_should_sign_extend() {
+        let point = point_from_message_pos(0xFFF6_0014);
+
+        assert_eq!((point.x, point.y), (20, -10));
+    }
+}
@@ -0,0 +1,95 @@
+//! Provides a watcher for session connect state transitions, built on [`NtUserRemoteConnectState`].
+//!
+//! Agents switching rendering or capture strategies when a remote desktop client connects to or disconnects from the
+//! session can either poll [`SessionWatcher::poll`] periodically via [`SessionWatcher::watch`], or call it whenever
+//! a `WM_WTSSESSION_CHANGE` notification is received.
+
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::{error::UserCallError, functions::NtUserRemoteConnectState};
+
+/// The connect state of the current session.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ConnectState {
+    /// The session is attached to the physical console.
+    Console,
+    /// The session is connected to a remote desktop client.
+    Remote,
+}
+
+impl ConnectState {
+    fn from_raw(state: usize) -> Self {
+        match state {
+            0 => Self::Console,
+            _ => Self::Remote,
+        }
+    }
+}
+
+/// Returns the connect state of the current session.
+pub fn connect_state() -> Result<ConnectState, UserCallError> {
+    // SAFETY: `NtUserRemoteConnectState` takes no parameters.
+    unsafe { NtUserRemoteConnectState() }.map(ConnectState::from_raw)
+}
+
+/// A transition of the session connect state, as reported by [`SessionWatcher`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SessionEvent {
+    /// The session has been connected to a remote desktop client.
+    RemoteConnected,
+    /// The session has been attached to the physical console.
+    ConsoleConnected,
+}
+
+/// Samples the session connect state and reports transitions between console and remote sessions.
+#[derive(Clone, Debug)]
+pub struct SessionWatcher {
+    state: ConnectState,
+}
+
+impl SessionWatcher {
+    /// Creates a new watcher, sampling the current connect state.
+    pub fn new() -> Result<Self, UserCallError> {
+        Ok(Self {
+            state: connect_state()?,
+        })
+    }
+
+    /// Returns the connect state as of the last poll.
+    pub fn state(&self) -> ConnectState {
+        self.state
+    }
+
+    /// Samples the connect state and returns the transition since the last poll, if any.
+    pub fn poll(&mut self) -> Result<Option<SessionEvent>, UserCallError> {
+        let state = connect_state()?;
+
+        if state == std::mem::replace(&mut self.state, state) {
+            return Ok(None);
+        }
+
+        Ok(Some(match state {
+            ConnectState::Console => SessionEvent::ConsoleConnected,
+            ConnectState::Remote => SessionEvent::RemoteConnected,
+        }))
+    }
+
+    /// Polls the connect state every `interval` and invokes `callback` for every transition until it returns
+    /// [`ControlFlow::Break`] or polling fails.
+    pub fn watch<B>(
+        &mut self,
+        interval: Duration,
+        mut callback: impl FnMut(SessionEvent) -> ControlFlow<B>,
+    ) -> Result<B, UserCallError> {
+        loop {
+            if let Some(event) = self.poll()? {
+                if let ControlFlow::Break(value) = callback(event) {
+                    return Ok(value);
+                }
+            }
+
+            std::thread::sleep(interval);
+        }
+    }
+}
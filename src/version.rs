@@ -1,107 +1,340 @@
-use std::sync::{LazyLock, OnceLock};
+use core::fmt::Display;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicU8, Ordering};
 
+#[cfg(feature = "std")]
 use windows::{
     Wdk::System::SystemServices::RtlGetVersion, Win32::System::SystemInformation::OSVERSIONINFOW,
 };
 
-use crate::error::UserCallError;
+use crate::{
+    error::UserCallError,
+    resolution::{find_export, Library},
+};
 
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OsVersion {
     #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))]
     Win7,
     Win8,
     Win81,
-    Win10,
+    Win10(Win10Build),
+}
+
+/// The Windows 10 releases whose `apfnSimpleCall` indices differ, named after their first version.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Win10Build {
+    /// Version 1507, build 10240.
+    V1507,
+    /// Version 1511, build 10586.
+    V1511,
+    /// Version 1607, build 14393.
+    V1607,
+    /// Version 1703, build 15063.
+    V1703,
+    /// Version 1709, build 16299.
+    V1709,
+    /// Version 1803, build 17134.
+    V1803,
+    /// Version 1809, build 17763.
+    V1809,
+    /// Version 1903 and newer, builds 18362 to 19045.
+    V1903,
+}
+
+impl Win10Build {
+    /// Returns the release `build` belongs to. Builds between releases, such as Insider Preview builds, belong to the
+    /// preceding release.
+    pub const fn from_build(build: u32) -> Self {
+        match build {
+            ..10586 => Self::V1507,
+            10586..14393 => Self::V1511,
+            14393..15063 => Self::V1607,
+            15063..16299 => Self::V1703,
+            16299..17134 => Self::V1709,
+            17134..17763 => Self::V1803,
+            17763..18362 => Self::V1809,
+            _ => Self::V1903,
+        }
+    }
 }
 
-static OS_VERSION: OnceLock<Result<OsVersion, UserCallError>> = OnceLock::new();
+/// The operating system version pinned via the `pin-*` features, whose indices are resolved at compile time.
+/// `pin-win10` pins Windows 10 version 1903 and newer.
+///
+/// If several versions are pinned, the newest one takes precedence. Pinning has no effect with `win11-only`.
+#[cfg(all(feature = "pin-win10", not(feature = "win11-only")))]
+pub(crate) const PINNED_OS_VERSION: Option<OsVersion> = Some(OsVersion::Win10(Win10Build::V1903));
+#[cfg(all(
+    feature = "pin-win81",
+    not(any(feature = "pin-win10", feature = "win11-only"))
+))]
+pub(crate) const PINNED_OS_VERSION: Option<OsVersion> = Some(OsVersion::Win81);
+#[cfg(all(
+    feature = "pin-win8",
+    not(any(feature = "pin-win10", feature = "pin-win81", feature = "win11-only"))
+))]
+pub(crate) const PINNED_OS_VERSION: Option<OsVersion> = Some(OsVersion::Win8);
+#[cfg(all(
+    feature = "pin-win7",
+    not(any(
+        feature = "pin-win10",
+        feature = "pin-win81",
+        feature = "pin-win8",
+        feature = "win11-only"
+    ))
+))]
+pub(crate) const PINNED_OS_VERSION: Option<OsVersion> = Some(OsVersion::Win7);
+#[cfg(any(
+    not(any(
+        feature = "pin-win7",
+        feature = "pin-win8",
+        feature = "pin-win81",
+        feature = "pin-win10"
+    )),
+    feature = "win11-only"
+))]
+pub(crate) const PINNED_OS_VERSION: Option<OsVersion> = None;
 
-static HAS_DEDICATED_SYSCALLS: LazyLock<bool> =
-    LazyLock::new(|| matches!(get_os_version(), Err(UserCallError::OsTooNew)));
+/// Returns the operating system version of [`crate::context::Context::global`].
+#[cfg(feature = "std")]
+pub(crate) fn get_os_version() -> Result<OsVersion, UserCallError> {
+    crate::context::Context::global().os_version()
+}
 
+/// Returns the operating system version. The build is read from the PEB on every call as that is as cheap as caching
+/// it, while the environment is detected once.
+#[cfg(not(feature = "std"))]
 pub(crate) fn get_os_version() -> Result<OsVersion, UserCallError> {
-    *OS_VERSION.get_or_init(|| {
-        let mut version_info = OSVERSIONINFOW {
-            dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as _,
-            ..Default::default()
-        };
-
-        // SAFETY: `version_info` is initialized with the correct size.
-        unsafe {
-            RtlGetVersion(&raw mut version_info).ok().unwrap();
+    if let Some(os_version) = PINNED_OS_VERSION {
+        return Ok(os_version);
+    }
+
+    detect_os_version()
+}
+
+/// Detects the operating system version without caching the result. Only the [`environment`] is cached.
+pub(crate) fn detect_os_version() -> Result<OsVersion, UserCallError> {
+    os_version_for(environment())
+}
+
+/// Detects the operating system version when running on `environment`.
+pub(crate) fn os_version_for(environment: Environment) -> Result<OsVersion, UserCallError> {
+    match environment {
+        Environment::Windows => {}
+        environment => return Err(UserCallError::UnsupportedEnvironment { environment }),
+    }
+
+    map_os_build(os_build())
+}
+
+/// The implementation of the Windows API the process is running on.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Environment {
+    Windows,
+    /// Wine, which reports the version of the Windows release it emulates, but implements the `NtUserCall*` family of
+    /// syscalls with its own indices.
+    Wine,
+    /// ReactOS, whose `apfnSimpleCall` indices do not match any Windows release. Only detected with the `std` feature
+    /// enabled.
+    ReactOs,
+}
+
+/// Detects the implementation of the Windows API the process is running on. The result is cached by
+/// [`crate::context::Context::global`] after the first call.
+///
+/// The functions in [`crate::functions`] fail with [`UserCallError::UnsupportedEnvironment`] outside of Windows, unless
+/// indices for the running build have been registered via `crate::indices::register_table`.
+#[cfg(feature = "std")]
+pub fn environment() -> Environment {
+    crate::context::Context::global().environment()
+}
+
+/// The detected [`Environment`] plus one, or zero if it has not been detected yet.
+#[cfg(not(feature = "std"))]
+static ENVIRONMENT: AtomicU8 = AtomicU8::new(0);
+
+/// Detects the implementation of the Windows API the process is running on. The result is cached after the first call.
+///
+/// The functions in [`crate::functions`] fail with [`UserCallError::UnsupportedEnvironment`] outside of Windows.
+#[cfg(not(feature = "std"))]
+pub fn environment() -> Environment {
+    match ENVIRONMENT.load(Ordering::Relaxed) {
+        1 => Environment::Windows,
+        2 => Environment::Wine,
+        3 => Environment::ReactOs,
+        _ => {
+            let environment = detect_environment();
+            ENVIRONMENT.store(environment as u8 + 1, Ordering::Relaxed);
+            environment
         }
+    }
+}
+
+/// Detects the implementation of the Windows API the process is running on without caching the result.
+pub(crate) fn detect_environment() -> Environment {
+    if find_export(Library::Ntdll, c"wine_get_version").is_ok() {
+        return Environment::Wine;
+    }
 
-        map_os_version_info(version_info)
-    })
+    #[cfg(feature = "std")]
+    if is_reactos(&os_version_info()) {
+        return Environment::ReactOs;
+    }
+
+    Environment::Windows
 }
 
-fn map_os_version_info(version_info: OSVERSIONINFOW) -> Result<OsVersion, UserCallError> {
-    match version_info {
-        OSVERSIONINFOW {
-            dwMajorVersion: ..6,
-            ..
-        } => Err(UserCallError::OsNotSupported),
+/// Returns whether `version_info` has been filled in by ReactOS, which appends its own version string after the
+/// terminating null character of the service pack string.
+#[cfg(feature = "std")]
+fn is_reactos(version_info: &OSVERSIONINFOW) -> bool {
+    let csd_version = &version_info.szCSDVersion;
+
+    csd_version
+        .iter()
+        .position(|&c| c == 0)
+        .and_then(|end| csd_version.get(end + 1..end + 8))
+        .is_some_and(|suffix| suffix.iter().copied().eq("ReactOS".encode_utf16()))
+}
+
+/// The version and build number of the operating system, as reported by `RtlGetVersion`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OsBuild {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+}
+
+impl Display for OsBuild {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.build)
+    }
+}
+
+/// Returns the version and build number of the operating system.
+#[cfg(feature = "std")]
+pub fn os_build() -> OsBuild {
+    let version_info = os_version_info();
+
+    OsBuild {
+        major: version_info.dwMajorVersion,
+        minor: version_info.dwMinorVersion,
+        build: version_info.dwBuildNumber,
+    }
+}
+
+/// Returns the version and build number of the operating system.
+#[cfg(not(feature = "std"))]
+pub fn os_build() -> OsBuild {
+    crate::peb::os_build()
+}
+
+#[cfg(feature = "std")]
+fn os_version_info() -> OSVERSIONINFOW {
+    let mut version_info = OSVERSIONINFOW {
+        dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as _,
+        ..Default::default()
+    };
+
+    // SAFETY: `version_info` is initialized with the correct size.
+    unsafe {
+        RtlGetVersion(&raw mut version_info).ok().unwrap();
+    }
+
+    version_info
+}
+
+fn map_os_build(os_build: OsBuild) -> Result<OsVersion, UserCallError> {
+    match os_build {
+        OsBuild { major: ..6, .. } => Err(UserCallError::OsNotSupported { os_build }),
         #[cfg(any(target_vendor = "win7", feature = "all_os_versions"))]
-        OSVERSIONINFOW {
-            dwMajorVersion: 6,
-            dwMinorVersion: 1,
-            ..
+        OsBuild {
+            major: 6, minor: 1, ..
         } => Ok(OsVersion::Win7),
-        OSVERSIONINFOW {
-            dwMajorVersion: 6,
-            dwMinorVersion: 2,
-            ..
+        OsBuild {
+            major: 6, minor: 2, ..
         } => Ok(OsVersion::Win8),
-        OSVERSIONINFOW {
-            dwMajorVersion: 6,
-            dwMinorVersion: 3,
-            ..
+        OsBuild {
+            major: 6, minor: 3, ..
         } => Ok(OsVersion::Win81),
-        OSVERSIONINFOW {
-            dwMajorVersion: 10,
-            dwMinorVersion: 0,
-            dwBuildNumber: ..20292,
+        OsBuild {
+            major: 10,
+            minor: 0,
+            build: build @ ..20292,
+        } => Ok(OsVersion::Win10(Win10Build::from_build(build))),
+        OsBuild {
+            major: 10..,
+            minor: 0,
             ..
-        } => Ok(OsVersion::Win10),
-        OSVERSIONINFOW {
-            dwMajorVersion: 10..,
-            dwMinorVersion: 0,
-            ..
-        } => Err(UserCallError::OsTooNew),
-        _ => Err(UserCallError::OsNotSupported),
+        } => Err(UserCallError::OsTooNew { os_build }),
+        _ => Err(UserCallError::OsNotSupported { os_build }),
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 pub fn set_os_version(
     os_version: Result<OsVersion, UserCallError>,
 ) -> Result<(), Result<OsVersion, UserCallError>> {
-    OS_VERSION.set(os_version)
+    crate::context::Context::global().os_version.set(os_version)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 pub fn set_os_version_info(
     version_info: OSVERSIONINFOW,
 ) -> Result<(), Result<OsVersion, UserCallError>> {
-    OS_VERSION.set(map_os_version_info(version_info))
+    crate::context::Context::global()
+        .os_version
+        .set(map_os_build(OsBuild {
+            major: version_info.dwMajorVersion,
+            minor: version_info.dwMinorVersion,
+            build: version_info.dwBuildNumber,
+        }))
+}
+
+/// Returns whether the current process is a 32-bit process running on a 64-bit operating system.
+#[cfg(all(target_arch = "x86", feature = "std"))]
+pub(crate) fn is_wow64() -> bool {
+    use windows::Win32::{
+        Foundation::BOOL,
+        System::Threading::{GetCurrentProcess, IsWow64Process},
+    };
+
+    let mut wow64 = BOOL::default();
+
+    // SAFETY: `wow64` is valid for writes.
+    unsafe { IsWow64Process(GetCurrentProcess(), &raw mut wow64) }.is_ok_and(|_| wow64.as_bool())
 }
 
+#[cfg(all(target_arch = "x86", not(feature = "std")))]
+pub(crate) use crate::peb::is_wow64;
+
 pub(crate) fn has_dedicated_syscalls() -> bool {
-    *HAS_DEDICATED_SYSCALLS
+    matches!(get_os_version(), Err(UserCallError::OsTooNew { .. }))
 }
 
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "std")]
     use windows::{
         core::{s, w, Owned},
-        Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+        Win32::System::{
+            LibraryLoader::{GetProcAddress, LoadLibraryW},
+            SystemInformation::OSVERSIONINFOW,
+        },
     };
 
-    use crate::version::has_dedicated_syscalls;
+    #[cfg(feature = "std")]
+    use crate::version::{has_dedicated_syscalls, is_reactos};
+    use crate::version::{map_os_build, OsBuild, OsVersion, Win10Build};
 
     #[test]
+    #[cfg(feature = "std")]
     pub fn has_dedicated_syscalls_should_match_dll() {
         let win32u =
             // SAFETY: `LoadLibraryW` is called with a valid LPCWSTR.
@@ -112,4 +345,48 @@ mod test {
 
         assert_eq!(function.is_some(), has_dedicated_syscalls());
     }
+
+    #[test]
+    pub fn map_os_build_should_split_win10_releases() {
+        for (build, release) in [
+            (10240, Win10Build::V1507),
+            (10586, Win10Build::V1511),
+            (14393, Win10Build::V1607),
+            (15063, Win10Build::V1703),
+            (16299, Win10Build::V1709),
+            (17134, Win10Build::V1803),
+            (17763, Win10Build::V1809),
+            (18362, Win10Build::V1903),
+            (19045, Win10Build::V1903),
+        ] {
+            assert_eq!(
+                map_os_build(OsBuild {
+                    major: 10,
+                    minor: 0,
+                    build,
+                }),
+                Ok(OsVersion::Win10(release))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    pub fn is_reactos_should_check_after_csd_version() {
+        let mut version_info = OSVERSIONINFOW::default();
+
+        for (dst, src) in version_info
+            .szCSDVersion
+            .iter_mut()
+            .zip("Service Pack 2\0ReactOS 0.4.15".encode_utf16())
+        {
+            *dst = src;
+        }
+
+        assert!(is_reactos(&version_info));
+
+        version_info.szCSDVersion[15] = 0;
+
+        assert!(!is_reactos(&version_info));
+    }
 }